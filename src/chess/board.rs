@@ -1,8 +1,8 @@
 use std::collections::HashMap;
 
 use crate::chess::{
-    piece::{Piece, PieceId, PiecePos},
-    standard::board::StandardBoard,
+    movement::{MoveKind, Target},
+    piece::{Piece, PieceId, PiecePos, Sign},
 };
 
 pub trait Board {
@@ -12,22 +12,174 @@ pub trait Board {
     fn get_board_size(&self) -> usize;
     fn get_id(&self, pos: &PiecePos<Self::PieceType>) -> Option<PieceId<Self::PieceType>>;
     fn get_id_not_none(&self, pos: &PiecePos<Self::PieceType>) -> Option<PieceId<Self::PieceType>>;
-    fn get_pos(&self, id: &PieceId<Self::PieceType>) -> Option<PiecePos<Self::PieceType>>;
+    fn get_pos(&self, id: &PieceId<Self::PieceType>) -> Option<PiecePos<'_, Self::PieceType>>;
     fn set_square(&mut self, id: &PieceId<Self::PieceType>, square: usize);
-    fn clear(&mut self);
+    fn add_piece(
+        &mut self,
+        piece: Self::PieceType,
+        sign: Sign,
+        square: usize,
+    ) -> PieceId<Self::PieceType>;
+    fn remove_piece(&mut self, id: &PieceId<Self::PieceType>);
+    /// Removes the piece most recently created by `add_piece`, so that its version
+    /// is handed out again by the next `add_piece`.
+    fn release_piece(&mut self, id: &PieceId<Self::PieceType>);
 }
 
+/// A board that a `Game` can be played on, as opposed to a view of one such as
+/// `BoardPreview`. It is set up from the piece code on every square and keeps a
+/// Zobrist key of the placement of its pieces.
+pub trait GameBoard: Board + Clone {
+    const ROW_SIZE: usize;
+    const COL_SIZE: usize;
+    const BOARD_SIZE: usize;
+    /// A board with the piece code of `state[u]` on every square `u`. Pieces with the
+    /// same code are told apart by the order of their squares.
+    fn from_state(state: &[isize]) -> Self;
+    fn hash(&self) -> u64;
+    /// The legal targets of the piece `id`, for boards that generate them faster
+    /// than the piece set walking its movesets would. `None` leaves it to the piece
+    /// set.
+    fn legal_targets(
+        &self,
+        _id: &PieceId<Self::PieceType>,
+        _history: &BoardHistory,
+    ) -> Option<Vec<Target>> {
+        None
+    }
+    /// Whether the side with `by` attacks `square`, for boards that can tell faster
+    /// than the piece set. `None` leaves it to the piece set.
+    fn is_attacked(&self, _square: usize, _by: Sign) -> Option<bool> {
+        None
+    }
+}
+
+/// A read-only view of a board with a single move applied on top of it, used to
+/// look ahead at a position without mutating the underlying board.
+pub struct BoardPreview<'a, P> {
+    base: &'a dyn Board<PieceType = P>,
+    moved: Vec<(PieceId<P>, usize, usize)>,
+    removed: Option<usize>,
+}
+
+impl<'a, P: Piece> BoardPreview<'a, P> {
+    pub fn new(
+        base: &'a dyn Board<PieceType = P>,
+        id: &PieceId<P>,
+        target: &Target,
+    ) -> Option<Self> {
+        let from = base.get_pos(id)?.u();
+        let mut moved = vec![(*id, from, target.u())];
+        let mut removed = None;
+        match target.kind() {
+            MoveKind::Castle(rook_from, rook_to) => {
+                let rook = base.get_id_not_none(&PiecePos(rook_from, base))?;
+                moved.push((rook, rook_from, rook_to));
+            }
+            MoveKind::EnPassant(captured) => removed = Some(captured),
+            MoveKind::Normal | MoveKind::DoubleStep(_) => (),
+        }
+        Some(BoardPreview {
+            base,
+            moved,
+            removed,
+        })
+    }
+}
+
+impl<'a, P: Piece> Board for BoardPreview<'a, P> {
+    type PieceType = P;
+
+    fn get_row_size(&self) -> usize {
+        self.base.get_row_size()
+    }
+
+    fn get_col_size(&self) -> usize {
+        self.base.get_col_size()
+    }
+
+    fn get_board_size(&self) -> usize {
+        self.base.get_board_size()
+    }
+
+    fn get_id(&self, pos: &PiecePos<P>) -> Option<PieceId<P>> {
+        let u = pos.u();
+        for (id, _, to) in &self.moved {
+            if *to == u {
+                return Some(*id);
+            }
+        }
+        if self.removed == Some(u) || self.moved.iter().any(|(_, from, _)| *from == u) {
+            return Some(PieceId::default());
+        }
+        self.base.get_id(pos)
+    }
+
+    fn get_id_not_none(&self, pos: &PiecePos<P>) -> Option<PieceId<P>> {
+        self.get_id(pos).filter(|id| !id.is_none())
+    }
+
+    fn get_pos(&self, id: &PieceId<P>) -> Option<PiecePos<'_, P>> {
+        for (moved, _, to) in &self.moved {
+            if moved == id {
+                return Some(PiecePos(*to, self));
+            }
+        }
+        let u = self.base.get_pos(id)?.u();
+        if self.removed == Some(u) || self.moved.iter().any(|(_, _, to)| *to == u) {
+            return None;
+        }
+        Some(PiecePos(u, self))
+    }
+
+    fn set_square(&mut self, _: &PieceId<P>, _: usize) {
+        panic!("a board preview is read-only");
+    }
+
+    fn add_piece(&mut self, _: P, _: Sign, _: usize) -> PieceId<P> {
+        panic!("a board preview is read-only");
+    }
+
+    fn remove_piece(&mut self, _: &PieceId<P>) {
+        panic!("a board preview is read-only");
+    }
+
+    fn release_piece(&mut self, _: &PieceId<P>) {
+        panic!("a board preview is read-only");
+    }
+}
+
+/// A move as it was played, with everything needed to take it back. Pieces are
+/// keyed the same way as in `BoardHistory`. Castling rights need no record of
+/// their own since they follow from the pieces' slices. `key` is the game's
+/// Zobrist key of everything but the placement of the pieces before the move.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct MoveRecord {
+    pub id: (isize, usize),
+    pub from: usize,
+    pub to: usize,
+    pub kind: MoveKind,
+    pub captured: Option<((isize, usize), usize)>,
+    pub promoted: Option<(isize, usize)>,
+    pub side_to_move: Sign,
+    pub en_passant: Option<usize>,
+    pub halfmove_clock: usize,
+    pub key: u64,
+}
+
+#[derive(Clone)]
 pub struct BoardHistory {
     pub past: HashMap<(isize, usize), BoardSlice>,
+    pub en_passant: Option<usize>,
+    pub moves: Vec<MoveRecord>,
 }
 
 impl BoardHistory {
     pub fn new(initial: Option<HashMap<(isize, usize), BoardSlice>>) -> Self {
-        match initial {
-            Some(past) => BoardHistory { past },
-            None => BoardHistory {
-                past: HashMap::new(),
-            },
+        BoardHistory {
+            past: initial.unwrap_or_default(),
+            en_passant: None,
+            moves: Vec::new(),
         }
     }
 
@@ -50,11 +202,19 @@ impl BoardHistory {
         }
     }
 
-    pub fn clear(&mut self) {
-        self.past = HashMap::new();
+    /// Takes back the last square pushed for the piece `id`.
+    pub fn pop<P: Piece>(&mut self, id: &PieceId<P>) {
+        let key = (id.i(), id.version());
+        if let Some(slice) = self.past.get_mut(&key) {
+            slice.0.pop();
+            if slice.inner().is_empty() {
+                self.past.remove(&key);
+            }
+        }
     }
 }
 
+#[derive(Clone)]
 pub struct BoardSlice(pub Vec<usize>);
 
 impl BoardSlice {
@@ -73,19 +233,14 @@ impl BoardSlice {
         self.0.push(pos);
     }
 
-    pub fn visualize<
-        const T_ROW_SIZE: usize,
-        const T_COL_SIZE: usize,
-        const T_BOARD_SIZE: usize,
-        P: Piece,
-    >(
-        &self,
-        fill: isize,
-    ) -> StandardBoard<T_ROW_SIZE, T_COL_SIZE, T_BOARD_SIZE, P> {
-        let mut visual = StandardBoard::new([0; T_BOARD_SIZE]);
+    pub fn visualize<B: GameBoard>(&self, fill: isize) -> B
+    where
+        B::PieceType: Piece,
+    {
+        let mut visual = B::from_state(&vec![0; B::BOARD_SIZE]);
         for v in &self.0 {
             visual.set_square(&PieceId(fill.into(), fill.into(), 0), *v);
         }
-        return visual;
+        visual
     }
 }
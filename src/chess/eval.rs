@@ -0,0 +1,11 @@
+use crate::chess::{
+    board::Board,
+    piece::{Piece, Sign},
+};
+
+/// Scores positions for searches and analysis.
+pub trait Evaluator<P: Piece> {
+    /// The score of the position on `board` in centipawns from the point of view of
+    /// `side`, the side to move.
+    fn evaluate(&self, board: &dyn Board<PieceType = P>, side: Sign) -> isize;
+}
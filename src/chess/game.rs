@@ -1,9 +1,62 @@
 use crate::chess::{
-    board::{Board, BoardHistory},
-    piece::{Piece, PieceId, PiecePos, PieceSet},
-    standard::board::StandardBoard,
+    board::{Board, BoardHistory, BoardSlice, GameBoard, MoveRecord},
+    eval::Evaluator,
+    movement::{MoveKind, Target},
+    piece::{Piece, PieceId, PiecePos, PieceSet, Sign},
+    square::{AlgebraicMove, Square},
+    standard::{
+        fen::{Fen, FenError, STARTING_FEN},
+        pgn::{result_tag, Pgn, PgnError, San},
+        piece::{StandardPiece, StandardPieceSet},
+    },
+    zobrist,
 };
 
+#[derive(Debug, PartialEq)]
+pub enum MoveError {
+    UnknownPiece,
+    IllegalDestination(usize),
+    WrongSide,
+    PromotionRequired,
+    IllegalPromotion,
+    OffBoard(Square),
+}
+
+impl std::fmt::Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoveError::UnknownPiece => write!(f, "the piece is not on the board"),
+            MoveError::IllegalDestination(square) => {
+                write!(f, "square {} is not a valid destination", square)
+            }
+            MoveError::WrongSide => write!(f, "it is not this side's turn to move"),
+            MoveError::PromotionRequired => write!(f, "the move requires a promotion piece"),
+            MoveError::IllegalPromotion => write!(f, "the piece cannot promote to that piece"),
+            MoveError::OffBoard(square) => write!(f, "{} is not on the board", square),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DrawRule {
+    FiftyMoves,
+    ThreefoldRepetition,
+    InsufficientMaterial,
+}
+
+/// The state of a game. Decisive results carry the sign of the winning side.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum GameResult {
+    Ongoing,
+    Checkmate(Sign),
+    Stalemate,
+    Draw(DrawRule),
+    Resignation(Sign),
+}
+
+#[derive(Clone)]
 pub struct Game<T, S>
 where
     T: Board,
@@ -12,69 +65,588 @@ where
     board: T,
     history: BoardHistory,
     piece_set: S,
+    side_to_move: Sign,
+    halfmove_clock: usize,
+    fullmove_number: usize,
+    /// The Zobrist key of the side to move, castling rights and en passant file.
+    key: u64,
+    analysis: bool,
+    flipped: bool,
+    captured: Vec<PieceId<T::PieceType>>,
+    /// The keys of the positions before each move played, for finding repetitions.
+    positions: Vec<u64>,
+    redo: Vec<MoveRecord>,
+    resigned: Option<Sign>,
 }
 
-impl<
-        const T_ROW_SIZE: usize,
-        const T_COL_SIZE: usize,
-        const T_BOARD_SIZE: usize,
-        P: Piece,
-        S: PieceSet<'static, PieceType = P>,
-    > Game<StandardBoard<T_ROW_SIZE, T_COL_SIZE, T_BOARD_SIZE, P>, S>
-{
-    pub fn new(initial_state: [isize; T_BOARD_SIZE], piece_set: S) -> Self {
-        Game {
-            board: StandardBoard::new(initial_state),
+impl<P: Piece, T: GameBoard<PieceType = P>, S: PieceSet<'static, PieceType = P>> Game<T, S> {
+    /// A game starting from the piece code of `initial_state[u]` on every square
+    /// `u`, with the first side to move.
+    pub fn new(initial_state: &[isize], piece_set: S) -> Self {
+        let mut game = Game {
+            board: T::from_state(initial_state),
             history: BoardHistory::new(None),
             piece_set,
+            side_to_move: Sign::Positive,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            key: 0,
+            analysis: false,
+            flipped: false,
+            captured: Vec::new(),
+            positions: Vec::new(),
+            redo: Vec::new(),
+            resigned: None,
+        };
+        game.key = game.state_key();
+        game
+    }
+
+    pub fn move_piece(&mut self, id: &PieceId<P>, square: usize) -> Result<(), MoveError> {
+        self.play(id, square, None)?;
+        self.redo.clear();
+        Ok(())
+    }
+
+    /// Moves a piece onto a promotion square, replacing it with `promotion`.
+    /// Returns the id of the newly created piece.
+    pub fn promote_piece(
+        &mut self,
+        id: &PieceId<P>,
+        square: usize,
+        promotion: P,
+    ) -> Result<PieceId<P>, MoveError> {
+        let promoted = self.play(id, square, Some(promotion))?;
+        self.redo.clear();
+        Ok(promoted)
+    }
+
+    /// Takes back the last move, restoring the exact previous position, along with
+    /// any resignation since. Returns whether there was a move to take back.
+    pub fn undo(&mut self) -> bool {
+        self.resigned = None;
+        match self.history.moves.pop() {
+            Some(record) => {
+                self.unmake_move(&record);
+                self.redo.push(record);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Plays `target`, one of the `legal_moves` of the piece `id`, without checking
+    /// that it is legal or adding it to the moves of the game. Returns the record
+    /// that `unmake_move` takes it back with. The move must be legal: this panics if
+    /// `id` is not on the board, and any other illegal move leaves the game in a
+    /// position that play could never reach.
+    pub fn make_move(
+        &mut self,
+        id: &PieceId<P>,
+        target: Target,
+        promotion: Option<P>,
+    ) -> MoveRecord {
+        let old_square = match self.board.get_pos(id) {
+            Some(old_pos) => old_pos.u(),
+            None => panic!("the piece to move is not on the board"),
+        };
+        let square = target.u();
+        let captured_square = match target.kind() {
+            MoveKind::EnPassant(captured) => captured,
+            _ => square,
+        };
+        let mut record = MoveRecord {
+            id: id.into(),
+            from: old_square,
+            to: square,
+            kind: target.kind(),
+            captured: None,
+            promoted: None,
+            side_to_move: self.side_to_move,
+            en_passant: self.history.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            key: self.key,
+        };
+        self.positions.push(self.hash());
+        let royal = self.piece_set.royal();
+        let castling_key = match self
+            .board
+            .get_id_not_none(&PiecePos(captured_square, &self.board))
+        {
+            _ if id.piece() == royal || self.piece_set.castles_with(&id.piece()) => {
+                Some(self.castling_key())
+            }
+            Some(other) if self.piece_set.castles_with(&other.piece()) => Some(self.castling_key()),
+            _ => None,
+        };
+        self.key ^= self.en_passant_key();
+        if let Some(other) = self
+            .board
+            .get_id_not_none(&PiecePos(captured_square, &self.board))
+        {
+            if other.opposes(id) {
+                record.captured = Some((other.into(), captured_square));
+                self.board.remove_piece(&other);
+                self.captured.push(other);
+            }
+        }
+        self.history.push(id, &PiecePos(square, &self.board));
+        self.history.en_passant = None;
+        self.board.set_square(&PieceId::default(), old_square);
+        match target.kind() {
+            MoveKind::Normal | MoveKind::EnPassant(_) => (),
+            MoveKind::Castle(rook_from, rook_to) => {
+                let rook_pos = PiecePos(rook_from, &self.board);
+                if let Some(rook) = self.board.get_id_not_none(&rook_pos) {
+                    self.history.push(&rook, &PiecePos(rook_to, &self.board));
+                    self.board.set_square(&PieceId::default(), rook_from);
+                    self.board.set_square(&rook, rook_to);
+                }
+            }
+            MoveKind::DoubleStep(passed) if self.can_take_en_passant(id, square, passed) => {
+                self.history.en_passant = Some(passed)
+            }
+            MoveKind::DoubleStep(_) => (),
+        }
+        match promotion {
+            Some(piece) => {
+                self.board.remove_piece(id);
+                let new_id = self.board.add_piece(piece, id.sign(), square);
+                // The new piece starts its own slice, so it never counts as unmoved.
+                self.history.push(&new_id, &PiecePos(square, &self.board));
+                record.promoted = Some(new_id.into());
+            }
+            None => self.board.set_square(id, square),
+        }
+        if record.captured.is_some() || self.piece_set.is_irreversible(&id.piece()) {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if id.sign() == Sign::Negative {
+            self.fullmove_number += 1;
+        }
+        if self.side_to_move != -id.sign() {
+            self.key ^= zobrist::side();
+        }
+        self.side_to_move = -id.sign();
+        self.key ^= self.en_passant_key();
+        if let Some(castling_key) = castling_key {
+            self.key ^= castling_key ^ self.castling_key();
+        }
+        record
+    }
+
+    /// Takes back a move played by `make_move`, which must be the last one played,
+    /// restoring the pieces with their identities, the castling rights, the clocks
+    /// and the Zobrist key exactly as they were.
+    pub fn unmake_move(&mut self, record: &MoveRecord) {
+        self.positions.pop();
+        let id: PieceId<P> = PieceId::from(record.id);
+        match record.promoted {
+            Some(promoted) => {
+                let promoted = PieceId::from(promoted);
+                self.board.release_piece(&promoted);
+                self.history.pop(&promoted);
+            }
+            None => self.board.set_square(&PieceId::default(), record.to),
+        }
+        if let MoveKind::Castle(rook_from, rook_to) = record.kind {
+            if let Some(rook) = self.board.get_id_not_none(&PiecePos(rook_to, &self.board)) {
+                self.history.pop(&rook);
+                self.board.set_square(&PieceId::default(), rook_to);
+                self.board.set_square(&rook, rook_from);
+            }
+        }
+        self.history.pop(&id);
+        self.board.set_square(&id, record.from);
+        if let Some((captured, square)) = record.captured {
+            let captured = PieceId::from(captured);
+            self.board.set_square(&captured, square);
+            self.captured.pop();
+        }
+        self.history.en_passant = record.en_passant;
+        self.side_to_move = record.side_to_move;
+        self.halfmove_clock = record.halfmove_clock;
+        self.key = record.key;
+        if id.sign() == Sign::Negative {
+            self.fullmove_number -= 1;
+        }
+    }
+
+    /// Plays again the last move taken back by `undo`. Returns whether there was a
+    /// move to replay.
+    pub fn redo(&mut self) -> bool {
+        let record = match self.redo.pop() {
+            Some(record) => record,
+            None => return false,
+        };
+        let promotion = record
+            .promoted
+            .map(|promoted| PieceId::<P>::from(promoted).piece());
+        let square = match record.kind {
+            MoveKind::Castle(rook_from, _) => rook_from,
+            _ => record.to,
+        };
+        let replayed = self.play(&PieceId::from(record.id), square, promotion);
+        if replayed.is_err() {
+            self.redo.clear();
+        }
+        replayed.is_ok()
+    }
+
+    /// The moves played so far, oldest first.
+    pub fn moves(&self) -> &[MoveRecord] {
+        &self.history.moves
+    }
+
+    /// Every legal move of the side with `sign`, ordered by the square the piece
+    /// moves from. Promotions appear once, whatever piece they promote to.
+    pub fn legal_moves(&self, sign: Sign) -> Vec<(PieceId<P>, Target)> {
+        let mut moves = Vec::new();
+        for u in 0..T::BOARD_SIZE {
+            if let Some(id) = self.board.get_id_not_none(&PiecePos(u, &self.board)) {
+                if id.sign() == sign {
+                    for target in self.valid_targets(&id) {
+                        moves.push((id, target));
+                    }
+                }
+            }
+        }
+        moves
+    }
+
+    /// The number of positions reached by every sequence of `depth` legal moves from
+    /// this one, counting every promotion piece as a move of its own. Comparing
+    /// these counts against known ones is how move generation is verified.
+    pub fn perft(&mut self, depth: usize) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let mut nodes = 0;
+        for (id, target) in self.legal_moves(self.side_to_move) {
+            let promotions = self.promotions(&id, target.u());
+            if depth == 1 {
+                nodes += promotions.len().max(1) as u64;
+                continue;
+            }
+            if promotions.is_empty() {
+                nodes += self.perft_after(&id, target, None, depth - 1);
+            }
+            for promotion in promotions {
+                nodes += self.perft_after(&id, target, Some(promotion), depth - 1);
+            }
+        }
+        nodes
+    }
+
+    fn perft_after(
+        &mut self,
+        id: &PieceId<P>,
+        target: Target,
+        promotion: Option<P>,
+        depth: usize,
+    ) -> u64 {
+        let record = self.make_move(id, target, promotion);
+        let nodes = self.perft(depth);
+        self.unmake_move(&record);
+        nodes
+    }
+
+    pub fn piece_at(&self, square: Square) -> Option<PieceId<P>> {
+        if !square.is_inbounds(T::ROW_SIZE, T::COL_SIZE) {
+            return None;
+        }
+        let pos = PiecePos(square.index(T::ROW_SIZE), &self.board);
+        self.board.get_id_not_none(&pos)
+    }
+
+    pub fn square_of(&self, id: &PieceId<P>) -> Option<Square> {
+        self.board.get_pos(id).map(|pos| pos.square())
+    }
+
+    pub fn piece_value(&self, piece: &P) -> isize {
+        self.piece_set.value(piece)
+    }
+
+    /// The pieces `id` may promote to when moving onto `square`, if any.
+    pub fn promotions(&self, id: &PieceId<P>, square: usize) -> Vec<P> {
+        self.piece_set.promotions(id, square, &self.board).to_vec()
+    }
+
+    pub fn move_piece_relative(
+        &mut self,
+        id: &PieceId<P>,
+        distance: usize,
+    ) -> Result<(), MoveError> {
+        let relative_square = match self.board.get_pos(id) {
+            Some(old_pos) => old_pos.offset(id.sign(), distance).u(),
+            None => return Err(MoveError::UnknownPiece),
+        };
+        self.move_piece(id, relative_square)
+    }
+
+    pub fn side_to_move(&self) -> Sign {
+        self.side_to_move
+    }
+
+    /// The Zobrist key of the position: the placement of the pieces, the side to
+    /// move, the castling rights and the file of a pawn that may be taken en
+    /// passant. Positions that are the same have the same key, however they came
+    /// about, and the key is kept up to date as moves are played and taken back.
+    pub fn hash(&self) -> u64 {
+        self.board.hash() ^ self.key
+    }
+
+    /// The squares of the pieces that their royal piece may still castle with, as
+    /// neither has moved yet.
+    pub fn castling_rights(&self) -> Vec<usize> {
+        let mut rights = Vec::new();
+        for u in 0..T::BOARD_SIZE {
+            let id = match self.board.get_id_not_none(&PiecePos(u, &self.board)) {
+                Some(id) => id,
+                None => continue,
+            };
+            if !self.piece_set.castles_with(&id.piece()) || self.history.get_slice(&id).is_some() {
+                continue;
+            }
+            let royal = PieceId(self.piece_set.royal(), id.sign(), 0);
+            if self.board.get_pos(&royal).is_some() && self.history.get_slice(&royal).is_none() {
+                rights.push(u);
+            }
+        }
+        rights
+    }
+
+    /// The key of what `hash` covers besides the placement of the pieces, worked
+    /// out from scratch.
+    fn state_key(&self) -> u64 {
+        let mut key = self.castling_key() ^ self.en_passant_key();
+        if self.side_to_move == Sign::Negative {
+            key ^= zobrist::side();
+        }
+        key
+    }
+
+    fn castling_key(&self) -> u64 {
+        self.castling_rights()
+            .into_iter()
+            .fold(0, |key, u| key ^ zobrist::castling(u))
+    }
+
+    /// Whether `id`, having just stepped two squares onto `square` through the
+    /// empty square `passed`, stands next to an opposing piece of its kind that may
+    /// take it en passant. Only then is the square it passed recorded, so that
+    /// positions differing in nothing else are the same.
+    fn can_take_en_passant(&self, id: &PieceId<P>, square: usize, passed: usize) -> bool {
+        if self
+            .board
+            .get_id_not_none(&PiecePos(passed, &self.board))
+            .is_some()
+        {
+            return false;
+        }
+        let x = square % T::ROW_SIZE;
+        [x.checked_sub(1), Some(x + 1).filter(|x| *x < T::ROW_SIZE)]
+            .into_iter()
+            .flatten()
+            .filter_map(|x| {
+                self.board
+                    .get_id_not_none(&PiecePos(square - square % T::ROW_SIZE + x, &self.board))
+            })
+            .any(|other| other.opposes(id) && other.piece() == id.piece())
+    }
+
+    fn en_passant_key(&self) -> u64 {
+        self.history
+            .en_passant
+            .map_or(0, |u| zobrist::en_passant(u % T::ROW_SIZE))
+    }
+
+    /// The number of moves since the last capture or irreversible piece move.
+    pub fn halfmove_clock(&self) -> usize {
+        self.halfmove_clock
+    }
+
+    pub fn fullmove_number(&self) -> usize {
+        self.fullmove_number
+    }
+
+    /// In analysis mode either side may move at any time, which allows setting up
+    /// positions freely. The side to move still follows the last moved piece.
+    pub fn set_analysis(&mut self, analysis: bool) {
+        self.analysis = analysis;
+    }
+
+    /// Turns the board around when visualizing it, so that it is printed from
+    /// Black's side: the first rank on top and the last file on the left.
+    pub fn set_flipped(&mut self, flipped: bool) {
+        self.flipped = flipped;
+    }
+
+    pub fn is_flipped(&self) -> bool {
+        self.flipped
+    }
+
+    /// Plays the move of `id` to `square` if it is legal. Castling is played by
+    /// moving onto the piece castled with or onto the square the royal piece lands
+    /// on. In Chess960 the royal piece may also step onto that square, and a plain
+    /// move there is preferred.
+    fn play(
+        &mut self,
+        id: &PieceId<P>,
+        square: usize,
+        promotion: Option<P>,
+    ) -> Result<PieceId<P>, MoveError> {
+        if self.board.get_pos(id).is_none() {
+            return Err(MoveError::UnknownPiece);
+        }
+        if !self.analysis && id.sign() != self.side_to_move {
+            return Err(MoveError::WrongSide);
+        }
+        let targets = self.valid_targets(id);
+        let target = targets
+            .iter()
+            .find(|target| matches!(target.kind(), MoveKind::Castle(rook, _) if rook == square))
+            .or_else(|| {
+                targets
+                    .iter()
+                    .filter(|target| target.u() == square)
+                    .min_by_key(|target| matches!(target.kind(), MoveKind::Castle(..)))
+            });
+        let target = match target {
+            Some(target) => *target,
+            None => return Err(MoveError::IllegalDestination(square)),
+        };
+        let promotions = self.piece_set.promotions(id, square, &self.board);
+        let promoted = match promotion {
+            Some(piece) if !promotions.contains(&piece) => return Err(MoveError::IllegalPromotion),
+            None if !promotions.is_empty() => return Err(MoveError::PromotionRequired),
+            _ => promotion,
+        };
+        let record = self.make_move(id, target, promoted);
+        self.history.moves.push(record);
+        Ok(record.promoted.map(PieceId::from).unwrap_or(*id))
+    }
+
+    /// The pieces of the side with `sign` that have been captured, in capture order.
+    pub fn captured(&self, sign: Sign) -> Vec<PieceId<P>> {
+        self.captured
+            .iter()
+            .filter(|id| id.sign() == sign)
+            .copied()
+            .collect()
+    }
+
+    /// The material on the board, positive when the positive side is ahead.
+    pub fn material_balance(&self) -> isize {
+        let mut balance = 0;
+        for u in 0..T::BOARD_SIZE {
+            if let Some(id) = self.board.get_id_not_none(&PiecePos(u, &self.board)) {
+                balance += id.sign() * self.piece_set.value(&id.piece());
+            }
+        }
+        balance
+    }
+
+    /// The score of the position by `evaluator`, from the point of view of the side
+    /// to move.
+    pub fn evaluate(&self, evaluator: &impl Evaluator<P>) -> isize {
+        evaluator.evaluate(&self.board, self.side_to_move)
+    }
+
+    pub fn is_in_check(&self, sign: Sign) -> bool {
+        let royal = PieceId(self.piece_set.royal(), sign, 0);
+        let square = match self.board.get_pos(&royal) {
+            Some(pos) => pos.u(),
+            None => return false,
+        };
+        self.board
+            .is_attacked(square, -sign)
+            .unwrap_or_else(|| self.piece_set.is_attacked(square, -sign, &self.board))
+    }
+
+    /// The legal targets of the piece `id`, from the board if it generates them
+    /// itself and from the piece set otherwise.
+    fn valid_targets(&self, id: &PieceId<P>) -> Vec<Target> {
+        self.board
+            .legal_targets(id, &self.history)
+            .unwrap_or_else(|| self.piece_set.valid_targets(id, &self.board, &self.history))
+    }
+
+    fn has_valid_moves(&self, sign: Sign) -> bool {
+        (0..T::BOARD_SIZE).any(|u| {
+            self.board
+                .get_id_not_none(&PiecePos(u, &self.board))
+                .is_some_and(|id| id.sign() == sign && !self.valid_targets(&id).is_empty())
+        })
+    }
+
+    pub fn resign(&mut self, sign: Sign) {
+        self.resigned = Some(sign);
+    }
+
+    /// How many times the current position has occurred, counting this time. Only
+    /// positions since the last capture or irreversible move can repeat it.
+    pub fn repetitions(&self) -> usize {
+        let hash = self.hash();
+        1 + self
+            .positions
+            .iter()
+            .rev()
+            .take(self.halfmove_clock)
+            .filter(|position| **position == hash)
+            .count()
+    }
+
+    /// The draw the rules call for in the current position, if any.
+    pub fn draw_rule(&self) -> Option<DrawRule> {
+        if self.piece_set.insufficient_material(&self.board) {
+            return Some(DrawRule::InsufficientMaterial);
+        }
+        if self.repetitions() >= 3 {
+            return Some(DrawRule::ThreefoldRepetition);
+        }
+        if self.halfmove_clock >= 100 {
+            return Some(DrawRule::FiftyMoves);
         }
+        None
     }
 
-    pub fn move_piece(&mut self, id: &PieceId<P>, square: usize) {
-        let pos = &PiecePos(square, &self.board);
-        let existing_piece = self.board.get_pos(id);
-        if existing_piece.is_some() {
-            let old_pos = existing_piece.unwrap();
-            let old_square = old_pos.u();
-            self.history.push(id, pos);
-            self.board.set_square(&PieceId::default(), old_square);
-            self.board.set_square(id, square);
-        }
-    }
-
-    pub fn move_piece_relative(&mut self, id: &PieceId<P>, distance: usize) {
-        let existing_piece = self.board.get_pos(id);
-        if existing_piece.is_some() {
-            let old_pos = existing_piece.unwrap();
-            let old_square = old_pos.u();
-            let relative_pos = old_pos.offset(id.sign(), distance);
-            let relative_square = relative_pos.u();
-            self.history.push(id, &relative_pos);
-            self.board.set_square(&PieceId::default(), old_square);
-            self.board.set_square(id, relative_square);
-        }
-    }
-
-    pub fn clear(&mut self) {
-        self.board.clear();
-        self.history.clear();
-    }
-
-    fn format_row(row: &[isize; T_ROW_SIZE]) -> String {
-        let closure = |v| match v {
-            &0 => ' ',
-            &1 => '♟',
-            &2 => '♞',
-            &3 => '♝',
-            &4 => '♜',
-            &5 => '♛',
-            &6 => '♚',
-            &-1 => '♙',
-            &-2 => '♘',
-            &-3 => '♗',
-            &-4 => '♖',
-            &-5 => '♕',
-            &-6 => '♔',
+    /// The state of the game. A move that checkmates or stalemates ends the game
+    /// that way even when it also completes a draw by rule.
+    pub fn status(&self) -> GameResult {
+        if let Some(resigned) = self.resigned {
+            return GameResult::Resignation(-resigned);
+        }
+        let sign = self.side_to_move;
+        if self.has_valid_moves(sign) {
+            return match self.draw_rule() {
+                Some(rule) => GameResult::Draw(rule),
+                None => GameResult::Ongoing,
+            };
+        }
+        if self.is_in_check(sign) {
+            return GameResult::Checkmate(-sign);
+        }
+        GameResult::Stalemate
+    }
+
+    fn format_row(row: &[isize]) -> String {
+        let closure = |v: &isize| match *v {
+            0 => ' ',
+            1 => '♟',
+            2 => '♞',
+            3 => '♝',
+            4 => '♜',
+            5 => '♛',
+            6 => '♚',
+            -1 => '♙',
+            -2 => '♘',
+            -3 => '♗',
+            -4 => '♖',
+            -5 => '♕',
+            -6 => '♔',
             _ => panic!("illegal state"),
         };
         row.iter()
@@ -82,13 +654,33 @@ impl<
             .fold(String::from(""), |acc, v| format!("{}{}", acc, v))
     }
 
-    fn print_board(board: &StandardBoard<T_ROW_SIZE, T_COL_SIZE, T_BOARD_SIZE, P>) {
+    /// The ranks of `board` as they are printed, from the top: the last rank first
+    /// with files from `a`, as White sees the board, or when `flipped` the first rank
+    /// first with files from the last, as Black sees it.
+    fn format_rows(board: &T, flipped: bool) -> Vec<String> {
+        let row = |y: usize| -> Vec<isize> {
+            (y * T::ROW_SIZE..(y + 1) * T::ROW_SIZE)
+                .map(|u| board.get_id(&PiecePos(u, board)).map_or(0, |id| id.i()))
+                .collect()
+        };
+        (0..T::COL_SIZE)
+            .map(|i| match flipped {
+                true => {
+                    let mut row = row(i);
+                    row.reverse();
+                    Self::format_row(&row)
+                }
+                false => Self::format_row(&row(T::COL_SIZE - 1 - i)),
+            })
+            .collect()
+    }
+
+    fn print_board(board: &T, flipped: bool) {
         let top_left_corner = String::from("┌");
         let top_right_corner = String::from("┐");
-        let line = String::from("─").repeat(T_ROW_SIZE);
+        let line = String::from("─").repeat(T::ROW_SIZE);
         println!("{}{}{}", top_left_corner, line, top_right_corner);
-        for i in 0..T_ROW_SIZE {
-            let formatted_row = Self::format_row(&board.row(i));
+        for formatted_row in Self::format_rows(board, flipped) {
             println!(" {} ", formatted_row);
         }
         let bottom_left_corner = String::from("└");
@@ -97,13 +689,712 @@ impl<
     }
 
     pub fn visualize_board(&self) {
-        Self::print_board(&self.board);
+        Self::print_board(&self.board, self.flipped);
     }
 
     pub fn visualize_moves(&self, id: &PieceId<P>) {
-        let slice = self.piece_set.valid_slice(id, &self.board, &self.history);
-        let mirror: StandardBoard<T_ROW_SIZE, T_COL_SIZE, T_BOARD_SIZE, P> =
-            slice.visualize(id.i());
-        Self::print_board(&mirror);
+        let targets = self.valid_targets(id);
+        let slice = BoardSlice::new(Some(targets.iter().map(Target::u).collect()));
+        let mirror: T = slice.visualize(id.i());
+        Self::print_board(&mirror, self.flipped);
+    }
+}
+
+impl<T: GameBoard<PieceType = StandardPiece>> Game<T, StandardPieceSet> {
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let fen = Fen::parse(fen, T::ROW_SIZE, T::COL_SIZE)?;
+        let mut game = Self::new(&fen.placement, StandardPieceSet);
+        game.side_to_move = fen.side_to_move;
+        game.halfmove_clock = fen.halfmove_clock;
+        game.fullmove_number = fen.fullmove_number;
+        game.history.en_passant = fen.en_passant.filter(|passed| {
+            let square = match fen.side_to_move {
+                Sign::Negative => Some(passed + T::ROW_SIZE),
+                _ => passed.checked_sub(T::ROW_SIZE),
+            };
+            square.filter(|u| *u < T::BOARD_SIZE).is_some_and(|u| {
+                game.board
+                    .get_id_not_none(&PiecePos(u, &game.board))
+                    .is_some_and(|id| {
+                        id.piece() == StandardPiece::Pawn
+                            && id.sign() != fen.side_to_move
+                            && game.can_take_en_passant(&id, u, *passed)
+                    })
+            })
+        });
+        for u in 0..T::BOARD_SIZE {
+            if let Some(id) = game.board.get_id_not_none(&PiecePos(u, &game.board)) {
+                let can_castle = match id.piece() {
+                    StandardPiece::King => fen
+                        .castling
+                        .iter()
+                        .any(|rook| Sign::from(fen.placement[*rook]) == id.sign()),
+                    StandardPiece::Rook => fen.castling.contains(&u),
+                    _ => true,
+                };
+                if !can_castle {
+                    game.history.push(&id, &PiecePos(u, &game.board));
+                }
+            }
+        }
+        game.key = game.state_key();
+        Ok(game)
+    }
+
+    pub fn to_fen(&self) -> String {
+        let placement = (0..T::BOARD_SIZE)
+            .map(|u| {
+                self.board
+                    .get_id(&PiecePos(u, &self.board))
+                    .map_or(0, |id| id.i())
+            })
+            .collect();
+        Fen {
+            row_size: T::ROW_SIZE,
+            placement,
+            side_to_move: self.side_to_move,
+            castling: self.castling_rights(),
+            en_passant: self.history.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+        }
+        .to_string()
+    }
+
+    /// The perft count of `depth` split by the legal move played first, for telling
+    /// which move a wrong count comes from.
+    pub fn divide(&mut self, depth: usize) -> Vec<(AlgebraicMove, u64)> {
+        let mut divided = Vec::new();
+        if depth == 0 {
+            return divided;
+        }
+        for (id, target) in self.legal_moves(self.side_to_move) {
+            let promotions = self.promotions(&id, target.u());
+            let promotions = match promotions.is_empty() {
+                true => vec![None],
+                false => promotions.into_iter().map(Some).collect(),
+            };
+            for promotion in promotions {
+                if let Some(algebraic) = self.algebraic(&id, &target, promotion) {
+                    let nodes = self.perft_after(&id, target, promotion, depth - 1);
+                    divided.push((algebraic, nodes));
+                }
+            }
+        }
+        divided
+    }
+
+    /// Replays the moves of `pgn` from its `FEN` tag, or from the starting position
+    /// when it has none. A decisive result that the moves do not explain is taken
+    /// as a resignation.
+    pub fn from_pgn(pgn: &Pgn) -> Result<Self, PgnError> {
+        let mut game = Self::from_fen(pgn.tag("FEN").unwrap_or(STARTING_FEN))?;
+        for san in &pgn.moves {
+            game.play_san(san)?;
+        }
+        if game.status() == GameResult::Ongoing {
+            match pgn.result() {
+                "1-0" => game.resign(Sign::Negative),
+                "0-1" => game.resign(Sign::Positive),
+                _ => (),
+            }
+        }
+        Ok(game)
+    }
+
+    /// Writes the move log as a game with the Seven Tag Roster left unknown, apart
+    /// from the result. The position is replayed from the first move to name each
+    /// move, which leaves the game as it was.
+    pub fn export_pgn(&mut self) -> Pgn {
+        let redo = std::mem::take(&mut self.redo);
+        let count = self.moves().len();
+        while self.undo() {}
+        let mut pgn = Pgn::new();
+        let fen = self.to_fen();
+        if fen != STARTING_FEN {
+            pgn.set_tag("SetUp", "1");
+            pgn.set_tag("FEN", &fen);
+        }
+        for _ in 0..count {
+            let record = match self.redo.last() {
+                Some(record) => *record,
+                None => break,
+            };
+            let promotion = record
+                .promoted
+                .map(|promoted| PieceId::<StandardPiece>::from(promoted).piece());
+            let target = Target(record.to, record.kind);
+            pgn.moves
+                .push(self.san(&PieceId::from(record.id), &target, promotion));
+            self.redo();
+        }
+        self.redo = redo;
+        pgn.set_tag("Result", result_tag(self.status()));
+        pgn
+    }
+
+    /// Plays a move given by the squares it connects, as parsed from long algebraic
+    /// notation such as "e2e4" or "e7e8q". Returns the id of the piece that moved,
+    /// or of the new piece for a promotion.
+    pub fn play_move(
+        &mut self,
+        algebraic: &AlgebraicMove,
+    ) -> Result<PieceId<StandardPiece>, MoveError> {
+        for square in [algebraic.from, algebraic.to] {
+            if !square.is_inbounds(T::ROW_SIZE, T::COL_SIZE) {
+                return Err(MoveError::OffBoard(square));
+            }
+        }
+        let id = self
+            .piece_at(algebraic.from)
+            .ok_or(MoveError::UnknownPiece)?;
+        let square = algebraic.to.index(T::ROW_SIZE);
+        match algebraic.promotion {
+            Some(symbol) => {
+                let piece =
+                    StandardPiece::from_symbol(symbol).ok_or(MoveError::IllegalPromotion)?;
+                self.promote_piece(&id, square, piece)
+            }
+            None => self.move_piece(&id, square).map(|_| id),
+        }
+    }
+
+    /// The long algebraic notation of a move found by `legal_moves`.
+    pub fn algebraic(
+        &self,
+        id: &PieceId<StandardPiece>,
+        target: &Target,
+        promotion: Option<StandardPiece>,
+    ) -> Option<AlgebraicMove> {
+        let from = self.square_of(id)?;
+        let to = Square::from_index(target.u(), T::ROW_SIZE);
+        Some(AlgebraicMove::new(
+            from,
+            to,
+            promotion.map(|piece| piece.symbol()),
+        ))
+    }
+
+    /// Plays a move given in Standard Algebraic Notation for the side to move.
+    pub fn play_san(&mut self, san: &str) -> Result<PieceId<StandardPiece>, PgnError> {
+        let parsed = San::parse(san, T::ROW_SIZE, T::COL_SIZE)?;
+        let candidates: Vec<(PieceId<StandardPiece>, Target)> = self
+            .legal_moves(self.side_to_move)
+            .into_iter()
+            .filter(|(id, target)| {
+                let from = match self.board.get_pos(id) {
+                    Some(pos) => pos.u(),
+                    None => return false,
+                };
+                match (parsed, target.kind()) {
+                    (San::Castle { kingside }, MoveKind::Castle(rook_from, _)) => {
+                        (rook_from > from) == kingside
+                    }
+                    (San::Castle { .. }, _) | (_, MoveKind::Castle(..)) => false,
+                    (
+                        San::Move {
+                            piece,
+                            file,
+                            rank,
+                            to,
+                            ..
+                        },
+                        _,
+                    ) => {
+                        id.piece() == piece
+                            && target.u() == to
+                            && file.is_none_or(|x| from % T::ROW_SIZE == x)
+                            && rank.is_none_or(|y| from / T::ROW_SIZE == y)
+                    }
+                }
+            })
+            .collect();
+        let (id, target) = match candidates[..] {
+            [candidate] => candidate,
+            [] => return Err(PgnError::IllegalMove(san.to_string())),
+            _ => return Err(PgnError::AmbiguousMove(san.to_string())),
+        };
+        let played = match parsed {
+            San::Move {
+                promotion: Some(piece),
+                ..
+            } => self.promote_piece(&id, target.u(), piece),
+            _ => match target.kind() {
+                MoveKind::Castle(rook_from, _) => self.move_piece(&id, rook_from),
+                _ => self.move_piece(&id, target.u()),
+            }
+            .map(|_| id),
+        };
+        played.map_err(|_| PgnError::IllegalMove(san.to_string()))
+    }
+
+    /// The Standard Algebraic Notation of a legal move, with the suffix for check or
+    /// checkmate. The move is played and taken back to find the suffix.
+    pub fn san(
+        &mut self,
+        id: &PieceId<StandardPiece>,
+        target: &Target,
+        promotion: Option<StandardPiece>,
+    ) -> String {
+        let from = match self.board.get_pos(id) {
+            Some(pos) => pos.u(),
+            None => return String::new(),
+        };
+        let mut san = String::new();
+        match target.kind() {
+            MoveKind::Castle(rook_from, _) if rook_from > from => san.push_str("O-O"),
+            MoveKind::Castle(..) => san.push_str("O-O-O"),
+            kind => {
+                let capture = matches!(kind, MoveKind::EnPassant(_))
+                    || self
+                        .board
+                        .get_id_not_none(&PiecePos(target.u(), &self.board))
+                        .is_some_and(|other| other.opposes(id));
+                let name = Square::from_index(from, T::ROW_SIZE).to_string();
+                let (file, rank) = name.split_at(1);
+                if id.piece() == StandardPiece::Pawn {
+                    if capture {
+                        san.push_str(file);
+                    }
+                } else {
+                    san.push(id.piece().symbol().to_ascii_uppercase());
+                    let others: Vec<usize> = self
+                        .legal_moves(id.sign())
+                        .iter()
+                        .filter(|(other, other_target)| {
+                            other.piece() == id.piece()
+                                && other != id
+                                && other_target.u() == target.u()
+                        })
+                        .filter_map(|(other, _)| self.board.get_pos(other).map(|pos| pos.u()))
+                        .collect();
+                    if others.iter().all(|u| u % T::ROW_SIZE != from % T::ROW_SIZE) {
+                        if !others.is_empty() {
+                            san.push_str(file);
+                        }
+                    } else if others.iter().all(|u| u / T::ROW_SIZE != from / T::ROW_SIZE) {
+                        san.push_str(rank);
+                    } else {
+                        san.push_str(file);
+                        san.push_str(rank);
+                    }
+                }
+                if capture {
+                    san.push('x');
+                }
+                san.push_str(&Square::from_index(target.u(), T::ROW_SIZE).to_string());
+                if let Some(piece) = promotion {
+                    san.push('=');
+                    san.push(piece.symbol().to_ascii_uppercase());
+                }
+            }
+        }
+        let record = self.make_move(id, *target, promotion);
+        let sign = self.side_to_move;
+        if self.is_in_check(sign) {
+            let mated = !self.has_valid_moves(sign);
+            san.push(if mated { '#' } else { '+' });
+        }
+        self.unmake_move(&record);
+        san
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DrawRule, Game, GameResult, MoveError};
+    use crate::chess::{
+        board::GameBoard,
+        piece::{PieceId, Sign},
+        square::Square,
+        standard::{
+            chess960,
+            fen::STARTING_FEN,
+            piece::{StandardPiece, StandardPieceSet},
+            BitBoardGame, StandardGame,
+        },
+    };
+
+    /// Checks the perft counts of `fen` for every depth from one up, on every kind
+    /// of board.
+    fn assert_perft(fen: &str, expected: &[u64]) {
+        let standard = perft_on(StandardGame::from_fen(fen).unwrap(), expected);
+        let bitboard = perft_on(BitBoardGame::from_fen(fen).unwrap(), expected);
+        assert_eq!(standard, bitboard);
+    }
+
+    /// Checks the perft counts of `game` and returns its FEN and key, which perft
+    /// should leave as they were.
+    fn perft_on<T: GameBoard<PieceType = StandardPiece>>(
+        mut game: Game<T, StandardPieceSet>,
+        expected: &[u64],
+    ) -> (String, u64) {
+        let before = (game.to_fen(), game.hash());
+        for (depth, nodes) in expected.iter().enumerate() {
+            let fen = &before.0;
+            assert_eq!(
+                game.perft(depth + 1),
+                *nodes,
+                "depth {} of {}",
+                depth + 1,
+                fen
+            );
+        }
+        assert_eq!((game.to_fen(), game.hash()), before);
+        before
+    }
+
+    /// Makes and unmakes every legal move of `game`, every promotion included, and
+    /// checks that the game is left exactly as it was. After every move the hash
+    /// kept up to date must equal that of the same position set up from scratch.
+    fn assert_unmake_restores<T: GameBoard<PieceType = StandardPiece>>(
+        mut game: Game<T, StandardPieceSet>,
+    ) {
+        let fresh_hash = |game: &Game<T, StandardPieceSet>| {
+            Game::<T, StandardPieceSet>::from_fen(&game.to_fen())
+                .unwrap()
+                .hash()
+        };
+        assert_eq!(game.hash(), fresh_hash(&game));
+        let sign = game.side_to_move();
+        let snapshot = |game: &Game<T, StandardPieceSet>| {
+            (
+                game.to_fen(),
+                game.hash(),
+                game.legal_moves(sign),
+                game.castling_rights(),
+            )
+        };
+        let before = snapshot(&game);
+        for (id, target) in game.legal_moves(sign) {
+            let mut promotions: Vec<_> = game
+                .promotions(&id, target.u())
+                .into_iter()
+                .map(Some)
+                .collect();
+            if promotions.is_empty() {
+                promotions.push(None);
+            }
+            for promotion in promotions {
+                let record = game.make_move(&id, target, promotion);
+                assert_eq!(game.side_to_move(), -sign);
+                assert_eq!(game.hash(), fresh_hash(&game), "{}", game.to_fen());
+                game.unmake_move(&record);
+                assert_eq!(snapshot(&game), before, "{:?} to {}", id, target.u());
+            }
+        }
+        assert!(game.moves().is_empty());
+    }
+
+    #[test]
+    fn unmake_move_restores_the_position() {
+        // Castling both ways, en passant, promotions with and without a capture,
+        // and double steps that may or may not be answered en passant.
+        for fen in [
+            "r3k2r/1P4P1/8/3pP3/8/8/1p4p1/R3K2R w KQkq d6 0 1",
+            "r3k2r/1P4P1/8/8/3pP3/8/1p4p1/R3K2R b KQkq e3 0 1",
+            "4k3/2p5/8/1P6/5p2/8/4P1P1/4K3 w - - 0 1",
+            "4k3/2p5/8/1P6/5p2/8/4P1P1/4K3 b - - 0 1",
+        ] {
+            assert_unmake_restores(StandardGame::from_fen(fen).unwrap());
+            assert_unmake_restores(BitBoardGame::from_fen(fen).unwrap());
+        }
+    }
+
+    #[test]
+    fn perft_starting_position() {
+        assert_perft(STARTING_FEN, &[20, 400, 8902]);
+    }
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    #[test]
+    fn perft_kiwipete() {
+        assert_perft(KIWIPETE, &[48, 2039, 97862]);
+    }
+
+    #[test]
+    fn perft_en_passant_and_pins() {
+        assert_perft(
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            &[14, 191, 2812, 43238],
+        );
+    }
+
+    #[test]
+    fn perft_promotions_and_castling() {
+        assert_perft(
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            &[6, 264, 9467],
+        );
+        assert_perft(
+            "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+            &[6, 264, 9467],
+        );
+    }
+
+    #[test]
+    fn perft_checks_and_discoveries() {
+        assert_perft(
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            &[44, 1486],
+        );
+        assert_perft(
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            &[46, 2079],
+        );
+    }
+
+    #[test]
+    fn perft_chess960() {
+        assert_perft(
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            &[21, 528, 12189],
+        );
+        assert_perft(
+            "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+            &[20, 479, 10471],
+        );
+    }
+
+    #[test]
+    fn chess960_start_positions() {
+        assert_eq!(chess960::start_fen(518).unwrap(), STARTING_FEN);
+        assert_eq!(
+            chess960::start_fen(0).unwrap(),
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1"
+        );
+        assert_eq!(chess960::start_fen(chess960::POSITIONS), None);
+        let mut ranks: Vec<String> = (0..chess960::POSITIONS)
+            .map(|index| chess960::start_fen(index).unwrap())
+            .collect();
+        ranks.sort();
+        ranks.dedup();
+        assert_eq!(ranks.len(), chess960::POSITIONS);
+    }
+
+    #[test]
+    fn chess960_castling() {
+        let fen = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+        let game = StandardGame::from_fen(fen).unwrap();
+        assert!(game.to_fen().contains(" w KQkq "));
+        let game = StandardGame::from_fen("4k3/8/8/8/8/8/8/R2RK3 w D - 0 1").unwrap();
+        assert!(game.to_fen().contains(" w D "));
+
+        // The king stepping onto the square it castles to is a plain move, castling
+        // is given by moving onto the rook.
+        let mut game = BitBoardGame::from_fen("4k3/8/8/8/8/8/8/5K1R w K - 0 1").unwrap();
+        game.play_move(&"f1g1".parse().unwrap()).unwrap();
+        assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/6KR b - - 1 1");
+        game.undo();
+        game.play_move(&"f1h1".parse().unwrap()).unwrap();
+        assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/5RK1 b - - 1 1");
+        game.undo();
+        game.redo();
+        assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/5RK1 b - - 1 1");
+
+        // A king already on its castling square castles by moving the rook alone.
+        let mut game = StandardGame::from_fen("4k3/8/8/8/8/8/8/6KR w K - 0 1").unwrap();
+        game.play_san("O-O").unwrap();
+        assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/5RK1 b - - 1 1");
+    }
+
+    #[test]
+    fn divide_adds_up_to_perft() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut game = StandardGame::from_fen(fen).unwrap();
+        let divided = game.divide(2);
+        assert_eq!(divided.len(), 48);
+        assert_eq!(divided.iter().map(|(_, nodes)| nodes).sum::<u64>(), 2039);
+        let castle = divided
+            .iter()
+            .find(|(algebraic, _)| algebraic.to_string() == "e1g1");
+        assert_eq!(castle.map(|(_, nodes)| *nodes), Some(43));
+    }
+
+    fn status_of(fen: &str) -> GameResult {
+        StandardGame::from_fen(fen).unwrap().status()
+    }
+
+    #[test]
+    fn board_orientation() {
+        let game = StandardGame::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        let rows = StandardGame::format_rows(&game.board, false);
+        assert_eq!(rows.first().unwrap(), "    ♔   ");
+        assert_eq!(rows.last().unwrap(), "♜   ♚   ");
+        let rows = StandardGame::format_rows(&game.board, true);
+        assert_eq!(rows.first().unwrap(), "   ♚   ♜");
+        assert_eq!(rows.last().unwrap(), "   ♔    ");
+    }
+
+    #[test]
+    fn threefold_repetition() {
+        let mut game = StandardGame::from_fen(STARTING_FEN).unwrap();
+        for san in ["Nf3", "Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1"] {
+            game.play_san(san).unwrap();
+            assert_eq!(game.status(), GameResult::Ongoing);
+        }
+        game.play_san("Ng8").unwrap();
+        assert_eq!(game.repetitions(), 3);
+        assert_eq!(
+            game.status(),
+            GameResult::Draw(DrawRule::ThreefoldRepetition)
+        );
+        game.undo();
+        assert_eq!(game.status(), GameResult::Ongoing);
+
+        // A double step no pawn can answer en passant leaves nothing to take, so
+        // the position after it is reached again by the knights alone.
+        let mut game = StandardGame::from_fen(STARTING_FEN).unwrap();
+        for san in ["e4", "Nf6", "Nf3", "Ng8", "Ng1", "Nf6", "Nf3", "Ng8"] {
+            game.play_san(san).unwrap();
+            assert_eq!(game.status(), GameResult::Ongoing);
+        }
+        game.play_san("Ng1").unwrap();
+        assert_eq!(game.repetitions(), 3);
+        assert_eq!(
+            game.status(),
+            GameResult::Draw(DrawRule::ThreefoldRepetition)
+        );
+    }
+
+    #[test]
+    fn en_passant_square_only_when_it_can_be_taken() {
+        let after_e4 = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1";
+        let mut game = StandardGame::from_fen(STARTING_FEN).unwrap();
+        game.play_san("e4").unwrap();
+        assert_eq!(game.to_fen(), after_e4);
+        let fen = after_e4.replace(" - ", " e3 ");
+        assert_eq!(StandardGame::from_fen(&fen).unwrap().to_fen(), after_e4);
+        for fen in [
+            "4k3/8/8/8/3nN3/8/8/4K3 b - e3 0 1",
+            "4k3/8/8/8/3pP3/4B3/8/4K3 b - e3 0 1",
+        ] {
+            let without = fen.replace(" e3 ", " - ");
+            assert_eq!(StandardGame::from_fen(fen).unwrap().to_fen(), without);
+        }
+
+        let answerable = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 2";
+        let mut game =
+            StandardGame::from_fen("rnbqkbnr/ppp1pppp/8/8/3p4/8/PPPPPPPP/RNBQKBNR w KQkq - 0 2")
+                .unwrap();
+        game.play_san("e4").unwrap();
+        assert_eq!(game.to_fen(), answerable);
+        assert_eq!(
+            StandardGame::from_fen(answerable).unwrap().to_fen(),
+            answerable
+        );
+    }
+
+    #[test]
+    fn checkmate_stalemate_and_resignation() {
+        let mut game = StandardGame::from_fen(STARTING_FEN).unwrap();
+        for san in ["f3", "e5", "g4"] {
+            game.play_san(san).unwrap();
+        }
+        game.play_san("Qh4").unwrap();
+        assert_eq!(game.status(), GameResult::Checkmate(Sign::Negative));
+        game.undo();
+        assert_eq!(game.status(), GameResult::Ongoing);
+
+        assert_eq!(
+            status_of("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"),
+            GameResult::Stalemate
+        );
+        assert_eq!(
+            status_of("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1"),
+            GameResult::Checkmate(Sign::Positive)
+        );
+
+        game.resign(Sign::Positive);
+        assert_eq!(game.status(), GameResult::Resignation(Sign::Negative));
+        assert!(game.undo());
+        assert_eq!(game.status(), GameResult::Ongoing);
+    }
+
+    #[test]
+    fn invalid_moves_are_refused() {
+        let mut game = StandardGame::from_fen(STARTING_FEN).unwrap();
+        let square = |name: &str| name.parse::<Square>().unwrap();
+        let knight = game.piece_at(square("g1")).unwrap();
+        let black_pawn = game.piece_at(square("e7")).unwrap();
+        assert_eq!(
+            game.move_piece(&black_pawn, square("e5").index(8)),
+            Err(MoveError::WrongSide)
+        );
+        assert_eq!(
+            game.move_piece(&knight, square("g3").index(8)),
+            Err(MoveError::IllegalDestination(square("g3").index(8)))
+        );
+        let missing = PieceId(StandardPiece::Queen, Sign::Positive, 1);
+        assert_eq!(
+            game.move_piece(&missing, square("d4").index(8)),
+            Err(MoveError::UnknownPiece)
+        );
+        assert_eq!(game.to_fen(), STARTING_FEN);
+        game.move_piece(&knight, square("f3").index(8)).unwrap();
+    }
+
+    #[test]
+    fn captured_pieces_are_given_back_on_undo() {
+        let mut game = StandardGame::from_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        let d5 = "d5".parse::<Square>().unwrap();
+        let pawn = game.piece_at("e4".parse().unwrap()).unwrap();
+        let black_pawn = game.piece_at(d5).unwrap();
+        game.move_piece(&pawn, d5.index(8)).unwrap();
+        assert_eq!(game.captured(Sign::Negative), vec![black_pawn]);
+        assert!(game.captured(Sign::Positive).is_empty());
+        assert!(game.undo());
+        assert!(game.captured(Sign::Negative).is_empty());
+        assert!(game.captured(Sign::Positive).is_empty());
+        assert_eq!(game.piece_at(d5), Some(black_pawn));
+    }
+
+    #[test]
+    fn analysis_mode_skips_the_turn_check() {
+        let mut game = StandardGame::from_fen(STARTING_FEN).unwrap();
+        let square = |name: &str| name.parse::<Square>().unwrap();
+        let e_pawn = game.piece_at(square("e2")).unwrap();
+        let d_pawn = game.piece_at(square("d2")).unwrap();
+        game.set_analysis(true);
+        game.move_piece(&e_pawn, square("e4").index(8)).unwrap();
+        game.move_piece(&d_pawn, square("d4").index(8)).unwrap();
+        assert_eq!(game.side_to_move(), Sign::Negative);
+        game.set_analysis(false);
+        assert_eq!(
+            game.move_piece(&e_pawn, square("e5").index(8)),
+            Err(MoveError::WrongSide)
+        );
+    }
+
+    #[test]
+    fn fifty_move_rule() {
+        let mut game = StandardGame::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80").unwrap();
+        assert_eq!(game.status(), GameResult::Ongoing);
+        game.play_san("Ra2").unwrap();
+        assert_eq!(game.status(), GameResult::Draw(DrawRule::FiftyMoves));
+        game.undo();
+        game.play_san("e4").unwrap();
+        assert_eq!(game.status(), GameResult::Ongoing);
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let insufficient = GameResult::Draw(DrawRule::InsufficientMaterial);
+        assert_eq!(status_of("4k3/8/8/8/8/8/8/4K3 w - - 0 1"), insufficient);
+        assert_eq!(status_of("4k3/8/8/8/8/8/8/4KN2 w - - 0 1"), insufficient);
+        assert_eq!(status_of("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1"), insufficient);
+        assert_eq!(
+            status_of("4k1b1/8/8/8/8/8/8/2B1K3 w - - 0 1"),
+            GameResult::Ongoing
+        );
+        assert_eq!(
+            status_of("4k3/8/8/8/8/8/8/3NKN2 w - - 0 1"),
+            GameResult::Ongoing
+        );
+        assert_eq!(
+            status_of("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"),
+            GameResult::Ongoing
+        );
     }
 }
@@ -1,5 +1,10 @@
 pub mod board;
+pub mod eval;
 pub mod game;
-pub mod standard;
 pub mod movement;
 pub mod piece;
+pub mod search;
+pub mod square;
+pub mod standard;
+pub mod transposition;
+pub mod zobrist;
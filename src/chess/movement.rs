@@ -8,19 +8,51 @@ struct MoveStep {
     y: isize,
 }
 
+/// What a move does besides relocating the moving piece.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MoveKind {
+    Normal,
+    /// Castling with the rook on the first square, which lands on the second.
+    Castle(usize, usize),
+    /// A double step that can be captured en passant on the passed square.
+    DoubleStep(usize),
+    /// An en passant capture of the piece on the given square.
+    EnPassant(usize),
+}
+
+/// A destination square produced by move generation and the kind of move reaching it.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Target(pub usize, pub MoveKind);
+
+impl Target {
+    pub fn u(&self) -> usize {
+        self.0
+    }
+
+    pub fn kind(&self) -> MoveKind {
+        self.1
+    }
+}
+
 pub struct Move {
     step: MoveStep,
     max_steps: usize,
+    kind: MoveKind,
 }
 
 impl Move {
     pub const fn new(step_x: isize, step_y: isize, max_steps: usize) -> Self {
+        Self::special(step_x, step_y, max_steps, MoveKind::Normal)
+    }
+
+    pub const fn special(step_x: isize, step_y: isize, max_steps: usize, kind: MoveKind) -> Self {
         Move {
             step: MoveStep {
                 x: step_x,
                 y: step_y,
             },
             max_steps,
+            kind,
         }
     }
 
@@ -34,22 +66,25 @@ impl Move {
         let mut calculated = Vec::new();
         let (px, py): (isize, isize) = piece_pos.into();
         let MoveStep { x, y } = self.step;
-        let mut mx = px as isize + x;
-        let mut my = py as isize + y;
+        let (x, y) = (piece_id.sign() * x, piece_id.sign() * y);
+        let mut mx = px + x;
+        let mut my = py + y;
         let mut iters: usize = 0;
         let mut captured: usize = 0;
 
         while iters < self.max_steps && PiecePos::is_inbounds(mx, my, board) {
             let from_xy = PiecePos::from((mx, my, board));
-            match board.get_id(&from_xy) {
-                Some(p) => {
-                    if !can_capture.check(piece_id, &p, &mut captured) {
-                        break;
-                    }
+            let mut blocked = false;
+            if let Some(p) = board.get_id(&from_xy) {
+                if !can_capture.check(piece_id, &p, &mut captured) {
+                    break;
                 }
-                None => (),
-            };
+                blocked = !p.is_none();
+            }
             calculated.push(from_xy.u());
+            if blocked {
+                break;
+            }
 
             mx += x;
             my += y;
@@ -57,13 +92,33 @@ impl Move {
         }
         calculated
     }
+
+    pub fn calculate_targets<P: Piece>(
+        &self,
+        piece_id: &PieceId<P>,
+        piece_pos: &PiecePos<P>,
+        can_capture: &CanCapture<P>,
+        board: &dyn Board<PieceType = P>,
+    ) -> Vec<Target> {
+        self.calculate(piece_id, piece_pos, can_capture, board)
+            .into_iter()
+            .map(|u| Target(u, self.kind))
+            .collect()
+    }
 }
 
+pub type CaptureCheck<P> = dyn Fn(&PieceId<P>, &PieceId<P>, &mut usize) -> bool;
+
+pub type MoveCondition<'a, P> = dyn Fn(
+    &PieceId<P>,
+    &dyn Board<PieceType = P>,
+    &BoardHistory,
+) -> Option<(Move, CanCapture<'a, P>)>;
+
 pub enum CanCapture<'a, P> {
     None,
-    Matching(usize),
     Opposing(usize),
-    Specific(&'a dyn Fn(&PieceId<P>, &PieceId<P>, &mut usize) -> bool),
+    Specific(&'a CaptureCheck<P>),
     All,
 }
 
@@ -71,25 +126,24 @@ impl<'a, P: Piece> CanCapture<'a, P> {
     pub fn check(&self, id: &PieceId<P>, other: &PieceId<P>, captured: &mut usize) -> bool {
         match self {
             CanCapture::None => other.is_none(),
-            CanCapture::Matching(max) => {
-                other.is_none() || (*captured < *max && (*captured += 1) == () && id.matches(other))
-            }
             CanCapture::Opposing(max) => {
-                other.is_none() || (*captured < *max && (*captured += 1) == () && id.opposes(other))
+                other.is_none() || Self::take(captured, *max) && id.opposes(other)
             }
             CanCapture::Specific(s) => s(id, other, captured),
             CanCapture::All => true,
         }
     }
+
+    fn take(captured: &mut usize, max: usize) -> bool {
+        if *captured < max {
+            *captured += 1;
+            return true;
+        }
+        false
+    }
 }
 
 pub enum CanMove<'a, P> {
     Free(Move, CanCapture<'a, P>),
-    Conditional(
-        &'a dyn Fn(
-            &PieceId<P>,
-            &dyn Board<PieceType = P>,
-            &BoardHistory,
-        ) -> Option<(Move, CanCapture<'a, P>)>,
-    ),
+    Conditional(&'a MoveCondition<'a, P>),
 }
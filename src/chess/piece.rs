@@ -1,6 +1,7 @@
 use crate::chess::{
-    board::{Board, BoardHistory, BoardSlice},
-    movement::CanMove,
+    board::{Board, BoardHistory, BoardPreview, BoardSlice},
+    movement::{CanMove, MoveKind, Target},
+    square::Square,
 };
 
 pub trait Piece: Copy + std::convert::From<isize> + std::convert::Into<isize> + PartialEq {
@@ -10,15 +11,81 @@ pub trait Piece: Copy + std::convert::From<isize> + std::convert::Into<isize> +
 pub trait PieceSet<'a> {
     type PieceType: Piece;
     fn moveset(&self, piece: &Self::PieceType) -> Option<&[CanMove<'a, Self::PieceType>]>;
-    fn valid_slice(
+    fn valid_targets(
         &self,
         piece_id: &PieceId<Self::PieceType>,
         board: &dyn Board<PieceType = Self::PieceType>,
         history: &BoardHistory,
+    ) -> Vec<Target>;
+    fn royal(&self) -> Self::PieceType;
+    /// Whether the royal piece may castle with `piece` while neither has moved.
+    fn castles_with(&self, piece: &Self::PieceType) -> bool;
+    fn value(&self, piece: &Self::PieceType) -> isize;
+    /// Whether moves of `piece` can never be reversed, which resets the halfmove clock.
+    fn is_irreversible(&self, piece: &Self::PieceType) -> bool;
+    /// Whether neither side has the material left to ever checkmate, whatever moves
+    /// are played.
+    fn insufficient_material(&self, board: &dyn Board<PieceType = Self::PieceType>) -> bool;
+    fn promotions(
+        &self,
+        piece_id: &PieceId<Self::PieceType>,
+        square: usize,
+        board: &dyn Board<PieceType = Self::PieceType>,
+    ) -> &[Self::PieceType];
+    fn attacks(
+        &self,
+        piece_id: &PieceId<Self::PieceType>,
+        board: &dyn Board<PieceType = Self::PieceType>,
     ) -> BoardSlice;
+
+    fn is_attacked(
+        &self,
+        square: usize,
+        by: Sign,
+        board: &dyn Board<PieceType = Self::PieceType>,
+    ) -> bool {
+        (0..board.get_board_size()).any(|u| {
+            board
+                .get_id_not_none(&PiecePos(u, board))
+                .is_some_and(|id| {
+                    id.sign() == by && self.attacks(&id, board).inner().contains(&square)
+                })
+        })
+    }
+
+    fn is_in_check(&self, sign: Sign, board: &dyn Board<PieceType = Self::PieceType>) -> bool {
+        match board.get_pos(&PieceId(self.royal(), sign, 0)) {
+            Some(pos) => self.is_attacked(pos.u(), -sign, board),
+            None => false,
+        }
+    }
+
+    /// Whether playing `target` keeps the mover's royal piece out of check. Castling
+    /// additionally requires the royal piece not to start in or pass through check.
+    fn leaves_royal_safe(
+        &self,
+        piece_id: &PieceId<Self::PieceType>,
+        target: &Target,
+        board: &dyn Board<PieceType = Self::PieceType>,
+    ) -> bool {
+        if let MoveKind::Castle(..) = target.kind() {
+            let from = match board.get_pos(piece_id) {
+                Some(pos) => pos.u(),
+                None => return false,
+            };
+            let (from, to) = (from.min(target.u()), from.max(target.u()));
+            if (from..=to).any(|u| self.is_attacked(u, -piece_id.sign(), board)) {
+                return false;
+            }
+        }
+        match BoardPreview::new(board, piece_id, target) {
+            Some(preview) => !self.is_in_check(piece_id.sign(), &preview),
+            None => false,
+        }
+    }
 }
 
-#[derive(PartialEq, PartialOrd, Clone, Copy)]
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
 pub enum Sign {
     None,
     Positive,
@@ -119,6 +186,7 @@ impl std::ops::Sub<Sign> for isize {
     }
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub struct PieceId<T>(pub T, pub Sign, pub usize);
 
 impl<P: Piece> From<(isize, usize)> for PieceId<P> {
@@ -206,11 +274,12 @@ impl<'a, P> PiecePos<'a, P> {
         self.0
     }
 
+    pub fn square(&self) -> Square {
+        Square::from_index(self.0, self.1.get_row_size())
+    }
+
     pub fn is_inbounds(x: isize, y: isize, board: &dyn Board<PieceType = P>) -> bool {
-        return x >= 0
-            && x < board.get_row_size() as isize
-            && y >= 0
-            && y < board.get_col_size() as isize;
+        x >= 0 && x < board.get_row_size() as isize && y >= 0 && y < board.get_col_size() as isize
     }
 
     pub fn top(&self, sign: Sign) -> Self {
@@ -237,28 +306,28 @@ impl<'a, P> PiecePos<'a, P> {
 
     pub fn topleft(&self, sign: Sign) -> Self {
         PiecePos(
-            (self.0 as isize + (sign * self.1.get_row_size() as isize) - 1) as usize,
+            (self.0 as isize + (sign * self.1.get_row_size() as isize) - sign * 1) as usize,
             self.1,
         )
     }
 
     pub fn topright(&self, sign: Sign) -> Self {
         PiecePos(
-            (self.0 as isize + (sign * self.1.get_row_size() as isize) + 1) as usize,
+            (self.0 as isize + (sign * self.1.get_row_size() as isize) + sign * 1) as usize,
             self.1,
         )
     }
 
     pub fn bottomleft(&self, sign: Sign) -> Self {
         PiecePos(
-            (self.0 as isize - (sign * self.1.get_row_size() as isize) - 1) as usize,
+            (self.0 as isize - (sign * self.1.get_row_size() as isize) - sign * 1) as usize,
             self.1,
         )
     }
 
     pub fn bottomright(&self, sign: Sign) -> Self {
         PiecePos(
-            (self.0 as isize - (sign * self.1.get_row_size() as isize) + 1) as usize,
+            (self.0 as isize - (sign * self.1.get_row_size() as isize) + sign * 1) as usize,
             self.1,
         )
     }
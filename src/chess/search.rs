@@ -0,0 +1,430 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::chess::{
+    board::GameBoard,
+    eval::Evaluator,
+    game::Game,
+    movement::{MoveKind, Target},
+    piece::{Piece, PieceId, PieceSet},
+    square::Square,
+    transposition::{Bound, TranspositionTable},
+};
+
+/// The score of delivering checkmate right away. Mates further away score one
+/// less for every ply it takes to deliver them.
+pub const MATE: isize = 100_000;
+pub const MAX_DEPTH: usize = 64;
+const INFINITY: isize = MATE + 1;
+
+/// A move as played by a search: the piece, the square it leaves, where it goes
+/// and the piece it promotes to, if any.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SearchMove<P> {
+    pub id: PieceId<P>,
+    pub from: usize,
+    pub target: Target,
+    pub promotion: Option<P>,
+}
+
+/// When a search has to stop. Without limits it runs until stopped from outside.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SearchLimits {
+    pub depth: Option<usize>,
+    pub time: Option<Duration>,
+}
+
+/// How long to think about a move with `remaining` time left on the clock, keeping
+/// `overhead` in reserve for the communication with the other side.
+pub fn time_budget(
+    remaining: Duration,
+    increment: Duration,
+    moves_to_go: Option<usize>,
+    overhead: Duration,
+) -> Duration {
+    let share = remaining / moves_to_go.unwrap_or(30).max(1) as u32 + increment * 3 / 4;
+    share.min(remaining.saturating_sub(overhead))
+}
+
+/// The outcome of a completed iteration of the search.
+#[derive(Debug, Clone)]
+pub struct SearchResult<P> {
+    pub best_move: Option<SearchMove<P>>,
+    /// The line of play both sides are expected to follow, starting with the best move.
+    pub pv: Vec<SearchMove<P>>,
+    /// The score in centipawns from the point of view of the side to move.
+    pub score: isize,
+    pub depth: usize,
+    pub nodes: u64,
+    pub time: Duration,
+}
+
+impl<P> SearchResult<P> {
+    /// The number of moves until the side to move mates, negative when it gets
+    /// mated, or `None` when the score is not a mate.
+    pub fn mate_in(&self) -> Option<isize> {
+        if self.score.abs() < MATE - MAX_DEPTH as isize {
+            return None;
+        }
+        let plies = MATE - self.score.abs();
+        Some(self.score.signum() * ((plies + 1) / 2))
+    }
+
+    pub fn nodes_per_second(&self) -> u64 {
+        (self.nodes as f64 / self.time.as_secs_f64().max(0.001)) as u64
+    }
+}
+
+/// A negamax search with alpha-beta pruning over the legal moves of a game,
+/// scoring the positions at its horizon with an [`Evaluator`] and deepened one ply
+/// at a time until a limit is reached or `stop` is raised. Positions already
+/// searched deeply enough are looked up in a transposition table instead. Each
+/// iteration tries the principal variation of the one before it first, followed
+/// by the best move the table knows of. The first iteration always runs to
+/// completion so that there is a move to play.
+pub struct Search<'a, P, E> {
+    limits: SearchLimits,
+    evaluator: E,
+    table: &'a mut TranspositionTable<P>,
+    stop: &'a AtomicBool,
+    deadline: Option<Instant>,
+    interruptible: bool,
+    nodes: u64,
+}
+
+impl<'a, P: Piece, E: Evaluator<P>> Search<'a, P, E> {
+    pub fn new(
+        limits: SearchLimits,
+        evaluator: E,
+        table: &'a mut TranspositionTable<P>,
+        stop: &'a AtomicBool,
+    ) -> Self {
+        Search {
+            limits,
+            evaluator,
+            table,
+            stop,
+            deadline: None,
+            interruptible: false,
+            nodes: 0,
+        }
+    }
+
+    /// Searches the position of `game` for the side to move, handing the result of
+    /// every completed iteration to `report`. Returns the result of the deepest one.
+    pub fn run<
+        T: GameBoard<PieceType = P>,
+        S: PieceSet<'static, PieceType = P>,
+        F: FnMut(&SearchResult<P>),
+    >(
+        &mut self,
+        game: &mut Game<T, S>,
+        mut report: F,
+    ) -> SearchResult<P> {
+        let started = Instant::now();
+        self.deadline = self.limits.time.map(|time| started + time);
+        self.nodes = 0;
+        self.table.new_search();
+        let mut result = SearchResult {
+            best_move: None,
+            pv: Vec::new(),
+            score: 0,
+            depth: 0,
+            nodes: 0,
+            time: Duration::ZERO,
+        };
+        for depth in 1..=self.limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH) {
+            self.interruptible = depth > 1;
+            let mut pv = Vec::new();
+            let score = self.negamax(game, depth, 0, -INFINITY, INFINITY, &mut pv, &result.pv);
+            let score = match score {
+                Some(score) => score,
+                None => break,
+            };
+            result = SearchResult {
+                best_move: pv.first().copied(),
+                pv,
+                score,
+                depth,
+                nodes: self.nodes,
+                time: started.elapsed(),
+            };
+            report(&result);
+            if result.best_move.is_none() || result.mate_in().is_some() {
+                break;
+            }
+        }
+        result.nodes = self.nodes;
+        result.time = started.elapsed();
+        result
+    }
+
+    /// The score of the position for the side to move within the window from
+    /// `alpha` to `beta`, or `None` if the search was interrupted. The best line
+    /// found is written to `pv`, while `follow` is the line to try first.
+    #[allow(clippy::too_many_arguments)]
+    fn negamax<T: GameBoard<PieceType = P>, S: PieceSet<'static, PieceType = P>>(
+        &mut self,
+        game: &mut Game<T, S>,
+        depth: usize,
+        ply: usize,
+        mut alpha: isize,
+        beta: isize,
+        pv: &mut Vec<SearchMove<P>>,
+        follow: &[SearchMove<P>],
+    ) -> Option<isize> {
+        self.nodes += 1;
+        pv.clear();
+        if self.is_interrupted() {
+            return None;
+        }
+        // A position repeated once is scored as the draw it would become if the
+        // line were played on, which keeps the search from walking into
+        // repetitions it could avoid.
+        if ply > 0 && (game.repetitions() > 1 || game.halfmove_clock() >= 100) {
+            return Some(0);
+        }
+        if depth == 0 {
+            return Some(game.evaluate(&self.evaluator));
+        }
+        let key = game.hash();
+        let entry = self.table.probe(key).copied();
+        if let Some(entry) = entry.filter(|entry| ply > 0 && entry.depth >= depth) {
+            let score = from_table(entry.score, ply);
+            // An exact score inside the window would become part of the principal
+            // variation, which the table cannot give back, so the node is searched.
+            let cutoff = match entry.bound {
+                Bound::Exact => score <= alpha || score >= beta,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if cutoff {
+                return Some(score);
+            }
+        }
+        let mut moves = Self::moves(game);
+        if moves.is_empty() {
+            if game.is_in_check(game.side_to_move()) {
+                return Some(-(MATE - ply as isize));
+            }
+            return Some(0);
+        }
+        let hinted = entry.and_then(|entry| entry.best_move);
+        Self::order(game, &mut moves, follow.first(), hinted.as_ref());
+        let original_alpha = alpha;
+        let mut line = Vec::new();
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        for search_move in moves {
+            let SearchMove {
+                id,
+                target,
+                promotion,
+                ..
+            } = search_move;
+            let record = game.make_move(&id, target, promotion);
+            let next = match follow.first() {
+                Some(first) if *first == search_move => &follow[1..],
+                _ => &[],
+            };
+            let score = self.negamax(game, depth - 1, ply + 1, -beta, -alpha, &mut line, next);
+            game.unmake_move(&record);
+            let score = -score?;
+            if score > best_score {
+                best_score = score;
+                best_move = Some(search_move);
+            }
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(search_move);
+                pv.extend_from_slice(&line);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.table
+            .store(key, depth, bound, to_table(best_score, ply), best_move);
+        Some(best_score)
+    }
+
+    /// The legal moves of the side to move, with a move for every promotion piece.
+    fn moves<T: GameBoard<PieceType = P>, S: PieceSet<'static, PieceType = P>>(
+        game: &Game<T, S>,
+    ) -> Vec<SearchMove<P>> {
+        let mut moves = Vec::new();
+        for (id, target) in game.legal_moves(game.side_to_move()) {
+            let from = match game.square_of(&id) {
+                Some(square) => square.index(T::ROW_SIZE),
+                None => continue,
+            };
+            let promotions = game.promotions(&id, target.u());
+            if promotions.is_empty() {
+                moves.push(SearchMove {
+                    id,
+                    from,
+                    target,
+                    promotion: None,
+                });
+            }
+            for promotion in promotions {
+                moves.push(SearchMove {
+                    id,
+                    from,
+                    target,
+                    promotion: Some(promotion),
+                });
+            }
+        }
+        moves
+    }
+
+    /// Puts the move of the previous principal variation first and the move from
+    /// the transposition table second, then captures of the most valuable pieces by
+    /// the least valuable ones, then promotions.
+    fn order<T: GameBoard<PieceType = P>, S: PieceSet<'static, PieceType = P>>(
+        game: &Game<T, S>,
+        moves: &mut [SearchMove<P>],
+        first: Option<&SearchMove<P>>,
+        hinted: Option<&SearchMove<P>>,
+    ) {
+        moves.sort_by_cached_key(|search_move| {
+            if Some(search_move) == first {
+                return isize::MIN;
+            }
+            if Some(search_move) == hinted {
+                return isize::MIN + 1;
+            }
+            let captured = match search_move.target.kind() {
+                MoveKind::EnPassant(square) => Some(square),
+                _ => Some(search_move.target.u()),
+            }
+            .and_then(|square| game.piece_at(Square::from_index(square, T::ROW_SIZE)));
+            let mut key = 0;
+            if let Some(captured) = captured {
+                key -= 100 * game.piece_value(&captured.piece())
+                    - game.piece_value(&search_move.id.piece())
+                    + 1000;
+            }
+            if let Some(promotion) = search_move.promotion {
+                key -= game.piece_value(&promotion);
+            }
+            key
+        });
+    }
+
+    fn is_interrupted(&self) -> bool {
+        if !self.interruptible {
+            return false;
+        }
+        self.stop.load(Ordering::Relaxed)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+/// Mate scores count the plies from the root of the search. The table stores them
+/// counted from the position itself, which may be reached at another ply later.
+fn to_table(score: isize, ply: usize) -> isize {
+    match score {
+        _ if score >= MATE - MAX_DEPTH as isize => score + ply as isize,
+        _ if score <= -(MATE - MAX_DEPTH as isize) => score - ply as isize,
+        _ => score,
+    }
+}
+
+fn from_table(score: isize, ply: usize) -> isize {
+    match score {
+        _ if score >= MATE - MAX_DEPTH as isize => score - ply as isize,
+        _ if score <= -(MATE - MAX_DEPTH as isize) => score + ply as isize,
+        _ => score,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+
+    use super::{Search, SearchLimits, SearchResult, MATE};
+    use crate::chess::{
+        square::Square,
+        standard::{eval::StandardEvaluator, piece::StandardPiece, BitBoardGame},
+        transposition::TranspositionTable,
+    };
+
+    /// Searches `fen` to `depth`, returning the result of every iteration.
+    fn search(fen: &str, depth: usize) -> Vec<SearchResult<StandardPiece>> {
+        search_with(&mut TranspositionTable::new(1), fen, depth)
+    }
+
+    /// Like `search`, but keeps what it learns in `table` for later searches.
+    fn search_with(
+        table: &mut TranspositionTable<StandardPiece>,
+        fen: &str,
+        depth: usize,
+    ) -> Vec<SearchResult<StandardPiece>> {
+        let mut game = BitBoardGame::from_fen(fen).unwrap();
+        let stop = AtomicBool::new(false);
+        let limits = SearchLimits {
+            depth: Some(depth),
+            time: None,
+        };
+        let mut iterations = Vec::new();
+        Search::new(limits, StandardEvaluator, table, &stop)
+            .run(&mut game, |result| iterations.push(result.clone()));
+        iterations
+    }
+
+    #[test]
+    fn mate_in_two() {
+        let iterations = search(
+            "r1b2k1r/ppp1bppp/8/1B1Q4/5q2/2P5/PPP2PPP/R3R1K1 w - - 1 1",
+            5,
+        );
+        let result = iterations.last().unwrap();
+        let best_move = result.best_move.unwrap();
+        assert_eq!(Square::from_index(best_move.from, 8), "d5".parse().unwrap());
+        assert_eq!(
+            Square::from_index(best_move.target.u(), 8),
+            "d8".parse().unwrap()
+        );
+        assert_eq!(result.score, MATE - 3);
+        assert_eq!(result.mate_in(), Some(2));
+    }
+
+    #[test]
+    fn table_hits_keep_the_whole_line() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+        let mut table = TranspositionTable::new(1);
+        search_with(&mut table, fen, 4);
+        for result in search_with(&mut table, fen, 4) {
+            assert_eq!(result.pv.len(), result.depth, "{:?}", result.pv);
+        }
+    }
+
+    #[test]
+    fn iterations_report_their_nodes() {
+        let iterations = search(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            4,
+        );
+        let depths: Vec<usize> = iterations.iter().map(|result| result.depth).collect();
+        assert_eq!(depths, [1, 2, 3, 4]);
+        // Every node of the first iteration is the root or one of its 20 moves.
+        assert_eq!(iterations[0].nodes, 21);
+        for pair in iterations.windows(2) {
+            assert!(pair[0].nodes < pair[1].nodes);
+        }
+    }
+}
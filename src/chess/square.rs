@@ -0,0 +1,205 @@
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq)]
+pub enum NotationError {
+    InvalidSquare(String),
+    InvalidMove(String),
+}
+
+impl std::fmt::Display for NotationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotationError::InvalidSquare(square) => write!(f, "'{}' is not a square", square),
+            NotationError::InvalidMove(text) => {
+                write!(f, "'{}' is not a move in long algebraic notation", text)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+/// A square named by its file and rank, both counted from zero. Files are lettered
+/// from `a` and ranks numbered from 1, so `e4` is `Square { x: 4, y: 3 }`, whatever
+/// the size of the board. Boards are indexed from the first file of the first rank.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Square {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Square {
+    pub const fn new(x: usize, y: usize) -> Self {
+        Square { x, y }
+    }
+
+    pub fn from_index(u: usize, row_size: usize) -> Self {
+        Square::new(u % row_size, u / row_size)
+    }
+
+    pub fn index(&self, row_size: usize) -> usize {
+        self.x + self.y * row_size
+    }
+
+    pub fn is_inbounds(&self, row_size: usize, col_size: usize) -> bool {
+        self.x < row_size && self.y < col_size
+    }
+
+    /// Parses a square name, returning its index on a board of the given size.
+    pub fn parse_index(name: &str, row_size: usize, col_size: usize) -> Option<usize> {
+        name.parse::<Square>()
+            .ok()
+            .filter(|square| square.is_inbounds(row_size, col_size))
+            .map(|square| square.index(row_size))
+    }
+
+    /// The name of file `x`: `a` to `z`, then `aa`, `ab` and so on, for boards
+    /// with more than 26 files.
+    pub fn file_name(x: usize) -> String {
+        let mut name = Vec::new();
+        let mut n = x + 1;
+        while n > 0 {
+            n -= 1;
+            name.push(b'a' + (n % 26) as u8);
+            n /= 26;
+        }
+        name.iter().rev().map(|c| *c as char).collect()
+    }
+
+    /// The file named by the lowercase letters of `name`, as written by `file_name`.
+    pub fn parse_file(name: &str) -> Option<usize> {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_lowercase()) {
+            return None;
+        }
+        name.bytes()
+            .try_fold(0usize, |x, c| {
+                x.checked_mul(26)?.checked_add((c - b'a') as usize + 1)
+            })
+            .map(|x| x - 1)
+    }
+
+    /// Parses the square at the start of `text`, returning it with the rest of the
+    /// text.
+    fn split(text: &str) -> Option<(Square, &str)> {
+        let letters = text.find(|c: char| !c.is_ascii_lowercase())?;
+        let digits = text[letters..]
+            .find(|c: char| !c.is_ascii_digit())
+            .map_or(text.len(), |i| letters + i);
+        let x = Square::parse_file(&text[..letters])?;
+        let y = text[letters..digits]
+            .parse::<usize>()
+            .ok()?
+            .checked_sub(1)?;
+        Some((Square::new(x, y), &text[digits..]))
+    }
+}
+
+impl FromStr for Square {
+    type Err = NotationError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match Square::split(name) {
+            Some((square, "")) => Ok(square),
+            _ => Err(NotationError::InvalidSquare(name.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for Square {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", Square::file_name(self.x), self.y + 1)
+    }
+}
+
+/// A move in long algebraic notation, naming the square a piece leaves and the one
+/// it lands on, followed by the lowercase symbol of the promotion piece, if any.
+/// Castling is written as the king's move.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct AlgebraicMove {
+    pub from: Square,
+    pub to: Square,
+    pub promotion: Option<char>,
+}
+
+impl AlgebraicMove {
+    pub fn new(from: Square, to: Square, promotion: Option<char>) -> Self {
+        AlgebraicMove {
+            from,
+            to,
+            promotion,
+        }
+    }
+}
+
+impl FromStr for AlgebraicMove {
+    type Err = NotationError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || NotationError::InvalidMove(text.to_string());
+        let (from, rest) = Square::split(text).ok_or_else(error)?;
+        let (to, rest) = Square::split(rest).ok_or_else(error)?;
+        let mut rest = rest.chars();
+        let promotion = match (rest.next(), rest.next()) {
+            (None, _) => None,
+            (Some(symbol), None) if symbol.is_ascii_alphabetic() => {
+                Some(symbol.to_ascii_lowercase())
+            }
+            _ => return Err(error()),
+        };
+        Ok(AlgebraicMove {
+            from,
+            to,
+            promotion,
+        })
+    }
+}
+
+impl std::fmt::Display for AlgebraicMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(promotion) = self.promotion {
+            write!(f, "{}", promotion)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AlgebraicMove, NotationError, Square};
+
+    #[test]
+    fn squares() {
+        assert_eq!("e4".parse(), Ok(Square::new(4, 3)));
+        assert_eq!("a10".parse(), Ok(Square::new(0, 9)));
+        assert_eq!("aa1".parse(), Ok(Square::new(26, 0)));
+        for x in [0, 7, 25, 26, 27, 51, 52, 701, 702, 1000] {
+            let square = Square::new(x, 11);
+            assert_eq!(square.to_string().parse(), Ok(square));
+        }
+        assert_eq!(Square::new(25, 0).to_string(), "z1");
+        assert_eq!(Square::new(26, 0).to_string(), "aa1");
+        assert_eq!(Square::new(701, 0).to_string(), "zz1");
+        assert_eq!(Square::parse_index("h8", 8, 8), Some(63));
+        assert_eq!(Square::parse_index("i1", 8, 8), None);
+        for name in ["", "e", "4", "E4", "e0", "e4x", "e-1", "4e"] {
+            let error = NotationError::InvalidSquare(name.to_string());
+            assert_eq!(name.parse::<Square>(), Err(error), "{}", name);
+        }
+    }
+
+    #[test]
+    fn moves() {
+        let e7e8q = AlgebraicMove::new(Square::new(4, 6), Square::new(4, 7), Some('q'));
+        assert_eq!("e7e8q".parse(), Ok(e7e8q));
+        assert_eq!("e7e8Q".parse(), Ok(e7e8q));
+        assert_eq!(e7e8q.to_string(), "e7e8q");
+        let wide = AlgebraicMove::new(Square::new(26, 9), Square::new(0, 10), None);
+        assert_eq!(wide.to_string(), "aa10a11");
+        assert_eq!("aa10a11".parse(), Ok(wide));
+        for text in ["", "e2", "e2e", "e2e4qq", "e2e4+", "e2-e4", "E2E4"] {
+            let error = NotationError::InvalidMove(text.to_string());
+            assert_eq!(text.parse::<AlgebraicMove>(), Err(error), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,471 @@
+use crate::chess::{
+    board::{Board, BoardHistory, GameBoard},
+    movement::{MoveKind, Target},
+    piece::{PieceId, PiecePos, Sign},
+    standard::{
+        magic::{bishop_attacks, queen_attacks, rook_attacks},
+        piece::StandardPiece,
+    },
+    zobrist,
+};
+
+const KNIGHT_OFFSETS: [(isize, isize); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const KING_OFFSETS: [(isize, isize); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+pub const DIAGONALS: [(isize, isize); 4] = [(1, 1), (-1, 1), (-1, -1), (1, -1)];
+pub const STRAIGHTS: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+/// The squares a knight attacks from every square.
+pub const KNIGHT_ATTACKS: [u64; 64] = leaper_attacks(&KNIGHT_OFFSETS);
+/// The squares a king attacks from every square.
+pub const KING_ATTACKS: [u64; 64] = leaper_attacks(&KING_OFFSETS);
+/// The squares a pawn attacks from every square, for white and then for black.
+pub const PAWN_ATTACKS: [[u64; 64]; 2] = [
+    leaper_attacks(&[(-1, 1), (1, 1)]),
+    leaper_attacks(&[(-1, -1), (1, -1)]),
+];
+
+const fn leaper_attacks(offsets: &[(isize, isize)]) -> [u64; 64] {
+    let mut attacks = [0; 64];
+    let mut u = 0;
+    while u < 64 {
+        let (x, y) = ((u % 8) as isize, (u / 8) as isize);
+        let mut i = 0;
+        while i < offsets.len() {
+            let (x, y) = (x + offsets[i].0, y + offsets[i].1);
+            if x >= 0 && x < 8 && y >= 0 && y < 8 {
+                attacks[u] |= 1 << (x + y * 8);
+            }
+            i += 1;
+        }
+        u += 1;
+    }
+    attacks
+}
+
+/// The squares attacked from `square` by a piece sliding in `directions` until it
+/// runs into an `occupied` square, which it attacks as well.
+pub fn sliding_attacks(square: usize, occupied: u64, directions: &[(isize, isize)]) -> u64 {
+    let mut attacks = 0;
+    for (dx, dy) in directions {
+        let (mut x, mut y) = ((square % 8) as isize + dx, (square / 8) as isize + dy);
+        while (0..8).contains(&x) && (0..8).contains(&y) {
+            let bit = 1 << (x + y * 8);
+            attacks |= bit;
+            if occupied & bit != 0 {
+                break;
+            }
+            x += dx;
+            y += dy;
+        }
+    }
+    attacks
+}
+
+/// Where the pieces of a side are kept in `BitBoard::sides`.
+pub fn side_index(sign: Sign) -> usize {
+    match sign {
+        Sign::Negative => 1,
+        _ => 0,
+    }
+}
+
+/// The squares set in `mask`, from a1 onwards.
+pub fn squares(mut mask: u64) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        let square = mask.trailing_zeros() as usize;
+        mask &= mask.wrapping_sub(1);
+        (square < 64).then_some(square)
+    })
+}
+
+/// Where the piece code `i` is kept in `BitBoard::repeats`.
+fn slot(i: isize) -> usize {
+    (i + 6) as usize
+}
+
+/// The standard eight by eight board as a set of 64-bit masks, one for each piece
+/// type and one for each side, next to the piece code on every square. Pieces are
+/// identified exactly as on `StandardBoard`, so that a game plays out the same on
+/// either board, only faster on this one.
+#[derive(Clone)]
+pub struct BitBoard {
+    state: [isize; 64],
+    /// The squares of each piece type, indexed by its code.
+    pieces: [u64; 7],
+    /// The squares of the white and of the black pieces.
+    sides: [u64; 2],
+    /// The square of every version of a piece code that has been on the board more
+    /// than once, indexed by `slot`. Empty for the other codes.
+    repeats: [Vec<Option<usize>>; 13],
+    hash: u64,
+}
+
+impl BitBoard {
+    pub fn empty() -> Self {
+        BitBoard {
+            state: [0; 64],
+            pieces: [0; 7],
+            sides: [0; 2],
+            repeats: Default::default(),
+            hash: 0,
+        }
+    }
+
+    pub fn side(&self, sign: Sign) -> u64 {
+        self.sides[side_index(sign)]
+    }
+
+    pub fn occupied(&self) -> u64 {
+        self.sides[0] | self.sides[1]
+    }
+
+    /// The squares of the pieces of the side with `sign` that attack `square`.
+    pub fn attackers(&self, square: usize, sign: Sign) -> u64 {
+        self.attackers_among(square, sign, self.occupied(), self.side(sign))
+    }
+
+    /// The squares among `side` of the pieces with `sign` that would attack `square`
+    /// if the `occupied` squares were the occupied ones.
+    fn attackers_among(&self, square: usize, sign: Sign, occupied: u64, side: u64) -> u64 {
+        let pieces = |piece: StandardPiece| self.pieces[isize::from(piece) as usize] & side;
+        let queens = pieces(StandardPiece::Queen);
+        KNIGHT_ATTACKS[square] & pieces(StandardPiece::Knight)
+            | KING_ATTACKS[square] & pieces(StandardPiece::King)
+            | PAWN_ATTACKS[side_index(-sign)][square] & pieces(StandardPiece::Pawn)
+            | bishop_attacks(square, occupied) & (pieces(StandardPiece::Bishop) | queens)
+            | rook_attacks(square, occupied) & (pieces(StandardPiece::Rook) | queens)
+    }
+
+    /// The legal destinations of the piece `id`, exactly those that its moveset in
+    /// `StandardPieceSet` allows, only found with lookups rather than by walking the
+    /// board square by square.
+    fn lookup_targets(&self, id: &PieceId<StandardPiece>, history: &BoardHistory) -> Vec<Target> {
+        let from = match self.get_pos(id) {
+            Some(pos) => pos.u(),
+            None => return Vec::new(),
+        };
+        let sign = id.sign();
+        let occupied = self.occupied();
+        let reachable = !self.side(sign);
+        let mut targets = Vec::new();
+        let attacks = match id.piece() {
+            StandardPiece::None => 0,
+            StandardPiece::Pawn => {
+                self.pawn_targets(from, sign, history, &mut targets);
+                PAWN_ATTACKS[side_index(sign)][from] & self.side(-sign)
+            }
+            StandardPiece::Knight => KNIGHT_ATTACKS[from],
+            StandardPiece::Bishop => bishop_attacks(from, occupied),
+            StandardPiece::Rook => rook_attacks(from, occupied),
+            StandardPiece::Queen => queen_attacks(from, occupied),
+            StandardPiece::King => {
+                self.castling_targets(id, from, history, &mut targets);
+                KING_ATTACKS[from]
+            }
+        };
+        targets.extend(squares(attacks & reachable).map(|to| Target(to, MoveKind::Normal)));
+        targets.retain(|target| self.leaves_king_safe(id, from, target));
+        targets
+    }
+
+    /// The single and double steps of a pawn on `from` and its en passant capture.
+    fn pawn_targets(
+        &self,
+        from: usize,
+        sign: Sign,
+        history: &BoardHistory,
+        targets: &mut Vec<Target>,
+    ) {
+        let occupied = self.occupied();
+        let step = |square: usize| {
+            Some(square as isize + sign * 8)
+                .filter(|to| (0..64).contains(to))
+                .map(|to| to as usize)
+        };
+        if let Some(passed) = step(from).filter(|to| occupied & 1 << to == 0) {
+            targets.push(Target(passed, MoveKind::Normal));
+            let rank = match sign {
+                Sign::Negative => 7 - from / 8,
+                _ => from / 8,
+            };
+            if let Some(to) = step(passed).filter(|to| rank == 1 && occupied & 1 << to == 0) {
+                targets.push(Target(to, MoveKind::DoubleStep(passed)));
+            }
+        }
+        if let Some(passed) = history.en_passant {
+            let captured = passed as isize - sign * 8;
+            if PAWN_ATTACKS[side_index(sign)][from] & 1 << passed != 0
+                && occupied & 1 << passed == 0
+                && (0..64).contains(&captured)
+                && self.state[captured as usize] == -sign * isize::from(StandardPiece::Pawn)
+            {
+                targets.push(Target(passed, MoveKind::EnPassant(captured as usize)));
+            }
+        }
+    }
+
+    /// Castling of an unmoved king with the nearest unmoved rook on either side,
+    /// by the Chess960 rules that `StandardPieceSet` follows.
+    fn castling_targets(
+        &self,
+        id: &PieceId<StandardPiece>,
+        from: usize,
+        history: &BoardHistory,
+        targets: &mut Vec<Target>,
+    ) {
+        if history.get_slice(id).is_some() {
+            return;
+        }
+        let rook = id.sign() * isize::from(StandardPiece::Rook);
+        let (x, y) = (from % 8, from / 8);
+        for (king_to, rook_to, files) in [
+            (2, 3, (0..x).rev().collect::<Vec<usize>>()),
+            (6, 5, (x + 1..8).collect()),
+        ] {
+            let rook_from = files.into_iter().map(|rx| rx + y * 8).find(|u| {
+                self.state[*u] == rook
+                    && self
+                        .get_id_not_none(&PiecePos(*u, self))
+                        .is_some_and(|other| history.get_slice(&other).is_none())
+            });
+            let rook_from = match rook_from {
+                Some(rook_from) => rook_from,
+                None => continue,
+            };
+            let rx = rook_from % 8;
+            let low = x.min(rx).min(king_to).min(rook_to);
+            let high = x.max(rx).max(king_to).max(rook_to);
+            let passed = ((1 << (high + 1)) - (1 << low)) << (y * 8);
+            if self.occupied() & passed & !(1 << from | 1 << rook_from) != 0 {
+                continue;
+            }
+            let kind = MoveKind::Castle(rook_from, rook_to + y * 8);
+            targets.push(Target(king_to + y * 8, kind));
+        }
+    }
+
+    /// Whether moving the piece `id` from `from` to `target` keeps the king of its
+    /// side out of check, and for castling, whether the king does not start in or
+    /// pass through check either.
+    fn leaves_king_safe(&self, id: &PieceId<StandardPiece>, from: usize, target: &Target) -> bool {
+        let sign = id.sign();
+        let to = target.u();
+        let mut occupied = self.occupied() & !(1 << from) | 1 << to;
+        let mut captured = 1 << to;
+        match target.kind() {
+            MoveKind::Castle(rook_from, rook_to) => {
+                if (from.min(to)..=from.max(to)).any(|u| self.attackers(u, -sign) != 0) {
+                    return false;
+                }
+                occupied = self.occupied() & !(1 << from | 1 << rook_from) | 1 << to | 1 << rook_to;
+            }
+            MoveKind::EnPassant(square) => {
+                occupied &= !(1 << square);
+                captured |= 1 << square;
+            }
+            MoveKind::Normal | MoveKind::DoubleStep(_) => (),
+        }
+        let king = PieceId(StandardPiece::King, sign, 0);
+        let square = match self.get_pos(&king) {
+            Some(_) if id == &king => to,
+            Some(pos) => pos.u(),
+            None => return true,
+        };
+        self.attackers_among(square, -sign, occupied, self.side(-sign) & !captured) == 0
+    }
+
+    fn put(&mut self, square: usize, i: isize) {
+        let bit = 1 << square;
+        let existing = self.state[square];
+        if existing != 0 {
+            self.pieces[existing.unsigned_abs()] &= !bit;
+            self.sides[side_index(Sign::from(existing))] &= !bit;
+        }
+        if i != 0 {
+            self.pieces[i.unsigned_abs()] |= bit;
+            self.sides[side_index(Sign::from(i))] |= bit;
+        }
+        self.hash ^= zobrist::piece(existing, square) ^ zobrist::piece(i, square);
+        self.state[square] = i;
+    }
+
+    /// The first square holding the piece code `i`.
+    fn first(&self, i: isize) -> Option<usize> {
+        let mask = self.pieces[i.unsigned_abs()] & self.sides[side_index(Sign::from(i))];
+        match mask {
+            0 => None,
+            _ => Some(mask.trailing_zeros() as usize),
+        }
+    }
+}
+
+impl Board for BitBoard {
+    type PieceType = StandardPiece;
+    fn get_row_size(&self) -> usize {
+        8
+    }
+
+    fn get_col_size(&self) -> usize {
+        8
+    }
+
+    fn get_board_size(&self) -> usize {
+        64
+    }
+
+    fn get_id(&self, pos: &PiecePos<StandardPiece>) -> Option<PieceId<StandardPiece>> {
+        let u = pos.u();
+        if u >= 64 {
+            return None;
+        }
+        let i = self.state[u];
+        let version = self.repeats[slot(i)]
+            .iter()
+            .position(|square| *square == Some(u))
+            .unwrap_or(0);
+        Some(PieceId::from((i, version)))
+    }
+
+    fn get_id_not_none(&self, pos: &PiecePos<StandardPiece>) -> Option<PieceId<StandardPiece>> {
+        self.get_id(pos).filter(|id| !id.is_none())
+    }
+
+    fn get_pos(&self, id: &PieceId<StandardPiece>) -> Option<PiecePos<'_, StandardPiece>> {
+        if id.is_none() {
+            return None;
+        }
+        let repeats = &self.repeats[slot(id.i())];
+        let square = match repeats.is_empty() {
+            true if id.version() == 0 => self.first(id.i()),
+            true => None,
+            false => repeats.get(id.version()).copied().flatten(),
+        };
+        square.map(|square| PiecePos(square, self))
+    }
+
+    fn set_square(&mut self, id: &PieceId<StandardPiece>, square: usize) {
+        let existing = self.state[square];
+        if existing != id.i() {
+            for pos in self.repeats[slot(existing)]
+                .iter_mut()
+                .filter(|pos| **pos == Some(square))
+            {
+                *pos = None;
+            }
+        }
+        let repeat = &mut self.repeats[slot(id.i())];
+        if !repeat.is_empty() {
+            if repeat.len() <= id.version() {
+                repeat.resize(id.version() + 1, None);
+            }
+            repeat[id.version()] = Some(square);
+        }
+        self.put(square, id.i());
+    }
+
+    fn add_piece(
+        &mut self,
+        piece: StandardPiece,
+        sign: Sign,
+        square: usize,
+    ) -> PieceId<StandardPiece> {
+        let i = isize::from(piece) * sign;
+        let existing = self.first(i);
+        let repeat = &mut self.repeats[slot(i)];
+        let version = match (repeat.is_empty(), existing) {
+            (false, _) => {
+                repeat.push(Some(square));
+                repeat.len() - 1
+            }
+            (true, Some(pos)) => {
+                *repeat = vec![Some(pos), Some(square)];
+                1
+            }
+            (true, None) => 0,
+        };
+        self.put(square, i);
+        PieceId(piece, sign, version)
+    }
+
+    fn remove_piece(&mut self, id: &PieceId<StandardPiece>) {
+        if let Some(square) = self.get_pos(id).map(|pos| pos.u()) {
+            self.put(square, 0);
+            if let Some(pos) = self.repeats[slot(id.i())].get_mut(id.version()) {
+                *pos = None;
+            }
+        }
+    }
+
+    fn release_piece(&mut self, id: &PieceId<StandardPiece>) {
+        self.remove_piece(id);
+        let repeat = &mut self.repeats[slot(id.i())];
+        if !repeat.is_empty() {
+            if repeat.len() == id.version() + 1 {
+                repeat.pop();
+            }
+            if repeat.len() == 1 {
+                repeat.clear();
+            }
+        }
+    }
+}
+
+impl GameBoard for BitBoard {
+    const ROW_SIZE: usize = 8;
+    const COL_SIZE: usize = 8;
+    const BOARD_SIZE: usize = 64;
+
+    fn from_state(state: &[isize]) -> Self {
+        assert_eq!(state.len(), 64, "a bitboard has 64 squares");
+        let mut board = BitBoard::empty();
+        let mut last_seen = [None; 13];
+        for (u, i) in state.iter().enumerate() {
+            if *i == 0 {
+                continue;
+            }
+            if let Some(previous) = last_seen[slot(*i)].replace(u) {
+                let repeat = &mut board.repeats[slot(*i)];
+                if repeat.is_empty() {
+                    repeat.push(Some(previous));
+                }
+                repeat.push(Some(u));
+            }
+            board.put(u, *i);
+        }
+        board
+    }
+
+    fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    fn legal_targets(
+        &self,
+        id: &PieceId<StandardPiece>,
+        history: &BoardHistory,
+    ) -> Option<Vec<Target>> {
+        Some(self.lookup_targets(id, history))
+    }
+
+    fn is_attacked(&self, square: usize, by: Sign) -> Option<bool> {
+        Some(self.attackers(square, by) != 0)
+    }
+}
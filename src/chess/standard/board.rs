@@ -1,9 +1,11 @@
 use crate::chess::{
-    board::{Board, BoardSlice},
-    piece::{Piece, PieceId, PiecePos},
+    board::{Board, GameBoard},
+    piece::{Piece, PieceId, PiecePos, Sign},
+    zobrist,
 };
 use std::{collections::HashMap, marker::PhantomData};
 
+#[derive(Clone)]
 pub struct StandardBoard<
     const T_ROW_SIZE: usize,
     const T_COL_SIZE: usize,
@@ -11,7 +13,8 @@ pub struct StandardBoard<
     P: Piece,
 > {
     state: [isize; T_BOARD_SIZE],
-    repeats: HashMap<isize, Vec<usize>>,
+    repeats: HashMap<isize, Vec<Option<usize>>>,
+    hash: u64,
     __: PhantomData<P>,
 }
 
@@ -19,45 +22,32 @@ impl<const T_ROW_SIZE: usize, const T_COL_SIZE: usize, const T_BOARD_SIZE: usize
     StandardBoard<T_ROW_SIZE, T_COL_SIZE, T_BOARD_SIZE, P>
 {
     pub fn new(initial_state: [isize; T_BOARD_SIZE]) -> Self {
-        let mut repeats: HashMap<isize, Vec<usize>> = HashMap::new();
+        let mut repeats: HashMap<isize, Vec<Option<usize>>> = HashMap::new();
         let mut last_seen_pos: HashMap<isize, usize> = HashMap::new();
         for (pos, id) in initial_state.iter().enumerate() {
             if id == &0 {
                 continue;
             }
-            match last_seen_pos.insert(*id, pos) {
-                Some(v) => {
-                    let repeat = repeats.entry(*id).or_insert(vec![v]);
-                    repeat.push(pos);
-                }
-                None => (),
+            if let Some(v) = last_seen_pos.insert(*id, pos) {
+                let repeat = repeats.entry(*id).or_insert(vec![Some(v)]);
+                repeat.push(Some(pos));
             }
         }
+        let hash = initial_state
+            .iter()
+            .enumerate()
+            .fold(0, |hash, (square, i)| hash ^ zobrist::piece(*i, square));
         StandardBoard {
             state: initial_state,
             repeats,
+            hash,
             __: PhantomData,
         }
     }
 
-    pub fn row(&self, row: usize) -> [isize; T_ROW_SIZE] {
-        assert!(row < T_ROW_SIZE, "the board only has {} rows", T_ROW_SIZE);
-        self.state[row * T_ROW_SIZE..row * T_ROW_SIZE + T_COL_SIZE]
-            .try_into()
-            .expect("unexpected slice length")
-    }
-
-    pub fn col(&self, col: usize) -> [isize; T_COL_SIZE] {
-        assert!(
-            col < T_COL_SIZE,
-            "the board only has {} columns",
-            T_COL_SIZE
-        );
-        let mut array = [0; T_COL_SIZE];
-        for u in 0..T_COL_SIZE {
-            array[u] = self.state[col + u * T_ROW_SIZE];
-        }
-        array
+    fn put(&mut self, square: usize, i: isize) {
+        self.hash ^= zobrist::piece(self.state[square], square) ^ zobrist::piece(i, square);
+        self.state[square] = i;
     }
 }
 
@@ -66,20 +56,20 @@ impl<const T_ROW_SIZE: usize, const T_COL_SIZE: usize, const T_BOARD_SIZE: usize
 {
     type PieceType = P;
     fn get_row_size(&self) -> usize {
-        return T_ROW_SIZE;
+        T_ROW_SIZE
     }
 
     fn get_col_size(&self) -> usize {
-        return T_COL_SIZE;
+        T_COL_SIZE
     }
 
     fn get_board_size(&self) -> usize {
-        return T_BOARD_SIZE;
+        T_BOARD_SIZE
     }
 
     fn get_id(&self, pos: &PiecePos<P>) -> Option<PieceId<P>> {
         let u = pos.u();
-        if u > T_BOARD_SIZE {
+        if u >= T_BOARD_SIZE {
             return None;
         }
         let id = self.state[u];
@@ -87,11 +77,11 @@ impl<const T_ROW_SIZE: usize, const T_COL_SIZE: usize, const T_BOARD_SIZE: usize
             .get(&id)
             .and_then(|repeats| {
                 for (version, existing_pos) in repeats.iter().enumerate() {
-                    if &u == existing_pos {
+                    if &Some(u) == existing_pos {
                         return Some(PieceId::from((id, version)));
                     }
                 }
-                return None;
+                None
             })
             .or(Some(PieceId::from((id, 0))))
     }
@@ -102,42 +92,115 @@ impl<const T_ROW_SIZE: usize, const T_COL_SIZE: usize, const T_BOARD_SIZE: usize
                 if !id.is_none() {
                     return Some(id);
                 }
-                return None;
+                None
             }
             None => None,
         }
     }
 
-    fn get_pos(&self, id: &PieceId<P>) -> Option<PiecePos<P>> {
+    fn get_pos(&self, id: &PieceId<P>) -> Option<PiecePos<'_, P>> {
         if id.is_none() {
             return None;
         }
-        self.repeats
-            .get(&id.i())
-            .and_then(|versions| {
-                versions.get(id.version()).and_then(|specific| {
-                    return Some(PiecePos(*specific, self));
-                })
-            })
-            .or_else(|| {
+        match self.repeats.get(&id.i()) {
+            Some(versions) => versions
+                .get(id.version())
+                .copied()
+                .flatten()
+                .map(|specific| PiecePos(specific, self)),
+            None => {
+                if id.version() != 0 {
+                    return None;
+                }
                 for (pos, i) in self.state.iter().enumerate() {
                     if i == &id.i() {
                         return Some(PiecePos(pos, self));
                     }
                 }
-                return None;
-            })
+                None
+            }
+        }
     }
 
     fn set_square(&mut self, id: &PieceId<P>, square: usize) {
-        self.repeats
-            .entry(id.i())
-            .and_modify(|repeat| repeat[id.version()] = square);
-        self.state[square] = id.i();
+        let existing = self.state[square];
+        if existing != id.i() {
+            if let Some(repeat) = self.repeats.get_mut(&existing) {
+                for pos in repeat.iter_mut().filter(|pos| **pos == Some(square)) {
+                    *pos = None;
+                }
+            }
+        }
+        self.repeats.entry(id.i()).and_modify(|repeat| {
+            if repeat.len() <= id.version() {
+                repeat.resize(id.version() + 1, None);
+            }
+            repeat[id.version()] = Some(square);
+        });
+        self.put(square, id.i());
+    }
+
+    fn add_piece(&mut self, piece: P, sign: Sign, square: usize) -> PieceId<P> {
+        let i = piece.into() * sign;
+        let existing = self.state.iter().position(|other| other == &i);
+        let version = match self.repeats.get_mut(&i) {
+            Some(repeat) => {
+                repeat.push(Some(square));
+                repeat.len() - 1
+            }
+            None => match existing {
+                Some(pos) => {
+                    self.repeats.insert(i, vec![Some(pos), Some(square)]);
+                    1
+                }
+                None => 0,
+            },
+        };
+        self.put(square, i);
+        PieceId(piece, sign, version)
+    }
+
+    fn remove_piece(&mut self, id: &PieceId<P>) {
+        if let Some(pos) = self.get_pos(id) {
+            let square = pos.u();
+            self.put(square, 0);
+            self.repeats
+                .entry(id.i())
+                .and_modify(|repeat| repeat[id.version()] = None);
+        }
+    }
+
+    fn release_piece(&mut self, id: &PieceId<P>) {
+        self.remove_piece(id);
+        if let Some(repeat) = self.repeats.get_mut(&id.i()) {
+            if repeat.len() == id.version() + 1 {
+                repeat.pop();
+            }
+            if repeat.len() == 1 {
+                self.repeats.remove(&id.i());
+            }
+        }
+    }
+}
+
+impl<const T_ROW_SIZE: usize, const T_COL_SIZE: usize, const T_BOARD_SIZE: usize, P: Piece>
+    GameBoard for StandardBoard<T_ROW_SIZE, T_COL_SIZE, T_BOARD_SIZE, P>
+{
+    const ROW_SIZE: usize = T_ROW_SIZE;
+    const COL_SIZE: usize = T_COL_SIZE;
+    const BOARD_SIZE: usize = T_BOARD_SIZE;
+
+    fn from_state(state: &[isize]) -> Self {
+        StandardBoard::new(
+            state
+                .try_into()
+                .expect("the board size must be the row size times the column size"),
+        )
     }
 
-    fn clear(&mut self) {
-        self.state = [0; T_BOARD_SIZE];
-        self.repeats = HashMap::new();
+    /// The Zobrist key of the placement of the pieces, kept up to date as pieces
+    /// are moved, added and removed.
+    fn hash(&self) -> u64 {
+        self.hash
     }
 }
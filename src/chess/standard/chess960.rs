@@ -0,0 +1,70 @@
+use crate::chess::standard::piece::StandardPiece;
+
+/// The number of Chess960 start positions.
+pub const POSITIONS: usize = 960;
+
+/// The places of the two knights among the five squares left once the bishops and
+/// the queen stand, for every remainder of the numbering.
+const KNIGHTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+/// The pieces of the back rank of the Chess960 start position numbered `index`,
+/// from the a-file, in the numbering of Reinhard Scharnagl under which 518 is the
+/// standard start position.
+pub fn back_rank(index: usize) -> Option<[StandardPiece; 8]> {
+    if index >= POSITIONS {
+        return None;
+    }
+    let mut rank = [StandardPiece::None; 8];
+    let mut n = index;
+    rank[n % 4 * 2 + 1] = StandardPiece::Bishop;
+    n /= 4;
+    rank[n % 4 * 2] = StandardPiece::Bishop;
+    n /= 4;
+    place(&mut rank, n % 6, StandardPiece::Queen);
+    let (first, second) = KNIGHTS[n / 6];
+    place(&mut rank, second, StandardPiece::Knight);
+    place(&mut rank, first, StandardPiece::Knight);
+    for piece in [
+        StandardPiece::Rook,
+        StandardPiece::King,
+        StandardPiece::Rook,
+    ] {
+        place(&mut rank, 0, piece);
+    }
+    Some(rank)
+}
+
+/// The FEN of the Chess960 start position numbered `index`.
+pub fn start_fen(index: usize) -> Option<String> {
+    let black: String = back_rank(index)?
+        .iter()
+        .map(|piece| piece.symbol())
+        .collect();
+    let white = black.to_ascii_uppercase();
+    Some(format!(
+        "{}/pppppppp/8/8/8/8/PPPPPPPP/{} w KQkq - 0 1",
+        black, white
+    ))
+}
+
+/// Puts `piece` on the empty square that has `skip` empty squares before it.
+fn place(rank: &mut [StandardPiece; 8], skip: usize, piece: StandardPiece) {
+    if let Some(square) = rank
+        .iter_mut()
+        .filter(|square| **square == StandardPiece::None)
+        .nth(skip)
+    {
+        *square = piece;
+    }
+}
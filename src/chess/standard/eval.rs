@@ -0,0 +1,254 @@
+use crate::chess::{
+    board::Board,
+    eval::Evaluator,
+    piece::{PiecePos, Sign},
+    square::Square,
+    standard::piece::StandardPiece,
+};
+
+/// The game phase with all minor and major pieces still on the board.
+const OPENING_PHASE: isize = 24;
+
+// Piece-square tables as seen by white, from a8 to h1. Black reads them mirrored.
+#[rustfmt::skip]
+const PAWN: [isize; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const PAWN_ENDGAME: [isize; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    80,  80,  80,  80,  80,  80,  80,  80,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    30,  30,  30,  30,  30,  30,  30,  30,
+    20,  20,  20,  20,  20,  20,  20,  20,
+    10,  10,  10,  10,  10,  10,  10,  10,
+    10,  10,  10,  10,  10,  10,  10,  10,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KNIGHT: [isize; 64] = [
+   -50, -40, -30, -30, -30, -30, -40, -50,
+   -40, -20,   0,   0,   0,   0, -20, -40,
+   -30,   0,  10,  15,  15,  10,   0, -30,
+   -30,   5,  15,  20,  20,  15,   5, -30,
+   -30,   0,  15,  20,  20,  15,   0, -30,
+   -30,   5,  10,  15,  15,  10,   5, -30,
+   -40, -20,   0,   5,   5,   0, -20, -40,
+   -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP: [isize; 64] = [
+   -20, -10, -10, -10, -10, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,  10,  10,   5,   0, -10,
+   -10,   5,   5,  10,  10,   5,   5, -10,
+   -10,   0,  10,  10,  10,  10,   0, -10,
+   -10,  10,  10,  10,  10,  10,  10, -10,
+   -10,   5,   0,   0,   0,   0,   5, -10,
+   -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK: [isize; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const QUEEN: [isize; 64] = [
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,   5,   5,   5,   0, -10,
+    -5,   0,   5,   5,   5,   5,   0,  -5,
+     0,   0,   5,   5,   5,   5,   0,  -5,
+   -10,   5,   5,   5,   5,   5,   0, -10,
+   -10,   0,   5,   0,   0,   0,   0, -10,
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const KING: [isize; 64] = [
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -20, -30, -30, -40, -40, -30, -30, -20,
+   -10, -20, -20, -20, -20, -20, -20, -10,
+    20,  20,   0,   0,   0,   0,  20,  20,
+    20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+#[rustfmt::skip]
+const KING_ENDGAME: [isize; 64] = [
+   -50, -40, -30, -20, -20, -30, -40, -50,
+   -30, -20, -10,   0,   0, -10, -20, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -30,   0,   0,   0,   0, -30, -30,
+   -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+/// How a piece is valued in the middlegame and the endgame, and how much it
+/// counts towards the game phase.
+struct Weights {
+    middlegame: isize,
+    endgame: isize,
+    table: &'static [isize; 64],
+    endgame_table: &'static [isize; 64],
+    phase: isize,
+}
+
+fn weights(piece: StandardPiece) -> Option<Weights> {
+    let (middlegame, endgame, table, endgame_table, phase) = match piece {
+        StandardPiece::None => return None,
+        StandardPiece::Pawn => (82, 94, &PAWN, &PAWN_ENDGAME, 0),
+        StandardPiece::Knight => (337, 281, &KNIGHT, &KNIGHT, 1),
+        StandardPiece::Bishop => (365, 297, &BISHOP, &BISHOP, 1),
+        StandardPiece::Rook => (477, 512, &ROOK, &ROOK, 2),
+        StandardPiece::Queen => (1025, 936, &QUEEN, &QUEEN, 4),
+        StandardPiece::King => (0, 0, &KING, &KING_ENDGAME, 0),
+    };
+    Some(Weights {
+        middlegame,
+        endgame,
+        table,
+        endgame_table,
+        phase,
+    })
+}
+
+/// The entry of the piece-square tables for a piece of `sign` on `square`. Boards
+/// of other sizes are scaled onto the eight by eight tables, so that a piece on the
+/// last rank of a larger board still reads the last rank of a table.
+fn table_index(square: Square, sign: Sign, row_size: usize, col_size: usize) -> usize {
+    let rank = match sign {
+        Sign::Positive => square.y,
+        _ => col_size - 1 - square.y,
+    };
+    (7 - rank * 8 / col_size) * 8 + square.x * 8 / row_size
+}
+
+/// Evaluates positions on boards of any size by material and piece-square tables,
+/// blending middlegame and endgame scores by how much material is left.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StandardEvaluator;
+
+impl Evaluator<StandardPiece> for StandardEvaluator {
+    fn evaluate(&self, board: &dyn Board<PieceType = StandardPiece>, side: Sign) -> isize {
+        let (row_size, col_size) = (board.get_row_size(), board.get_col_size());
+        let (mut middlegame, mut endgame, mut phase) = (0, 0, 0);
+        for u in 0..board.get_board_size() {
+            let id = match board.get_id_not_none(&PiecePos(u, board)) {
+                Some(id) => id,
+                None => continue,
+            };
+            let weights = match weights(id.piece()) {
+                Some(weights) => weights,
+                None => continue,
+            };
+            let i = table_index(
+                Square::from_index(u, row_size),
+                id.sign(),
+                row_size,
+                col_size,
+            );
+            middlegame += id.sign() * (weights.middlegame + weights.table[i]);
+            endgame += id.sign() * (weights.endgame + weights.endgame_table[i]);
+            phase += weights.phase;
+        }
+        let phase = phase.min(OPENING_PHASE);
+        side * ((middlegame * phase + endgame * (OPENING_PHASE - phase)) / OPENING_PHASE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{table_index, StandardEvaluator};
+    use crate::chess::{
+        game::Game,
+        piece::Sign,
+        square::Square,
+        standard::{
+            board::StandardBoard,
+            piece::{StandardPiece, StandardPieceSet},
+            StandardGame,
+        },
+    };
+
+    /// The score of the position with `placement` for White.
+    fn white_score(placement: &str) -> isize {
+        StandardGame::from_fen(&format!("{} w - - 0 1", placement))
+            .unwrap()
+            .evaluate(&StandardEvaluator)
+    }
+
+    /// `placement` turned upside down with the colours swapped.
+    fn mirror(placement: &str) -> String {
+        let ranks: Vec<String> = placement
+            .split('/')
+            .rev()
+            .map(|rank| {
+                rank.chars()
+                    .map(|c| match c.is_ascii_uppercase() {
+                        true => c.to_ascii_lowercase(),
+                        false => c.to_ascii_uppercase(),
+                    })
+                    .collect()
+            })
+            .collect();
+        ranks.join("/")
+    }
+
+    #[test]
+    fn mirrored_positions_score_opposite() {
+        for placement in [
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8",
+            "6k1/8/8/8/8/8/5PPP/6K1",
+        ] {
+            assert_eq!(
+                white_score(placement),
+                -white_score(&mirror(placement)),
+                "{}",
+                placement
+            );
+        }
+    }
+
+    #[test]
+    fn larger_boards_scale_onto_the_tables() {
+        let corners: Vec<usize> = [(0, 0), (9, 0), (0, 9), (9, 9)]
+            .into_iter()
+            .map(|(x, y)| table_index(Square::new(x, y), Sign::Positive, 10, 10))
+            .collect();
+        assert_eq!(corners, [56, 63, 0, 7]);
+        assert_eq!(
+            table_index(Square::new(3, 1), Sign::Negative, 10, 10),
+            table_index(Square::new(3, 8), Sign::Positive, 10, 10)
+        );
+        let game = Game::<StandardBoard<10, 10, 100, StandardPiece>, StandardPieceSet>::from_fen(
+            "4k5/10/10/10/10/10/10/10/10/4K5 w - - 0 1",
+        )
+        .unwrap();
+        assert_eq!(game.evaluate(&StandardEvaluator), 0);
+    }
+}
@@ -0,0 +1,358 @@
+use crate::chess::{piece::Sign, square::Square, standard::piece::StandardPiece};
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Debug, PartialEq)]
+pub enum FenError {
+    MissingField(&'static str),
+    RankCount(usize),
+    RankLength(usize, usize),
+    UnknownPiece(char),
+    SideToMove(String),
+    Castling(String),
+    EnPassant(String),
+    Clock(String),
+}
+
+impl std::fmt::Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FenError::MissingField(field) => write!(f, "the FEN is missing the {} field", field),
+            FenError::RankCount(count) => write!(f, "the FEN describes {} ranks", count),
+            FenError::RankLength(rank, length) => {
+                write!(f, "rank {} of the FEN describes {} squares", rank, length)
+            }
+            FenError::UnknownPiece(symbol) => write!(f, "'{}' is not a piece", symbol),
+            FenError::SideToMove(side) => write!(f, "'{}' is not a side to move", side),
+            FenError::Castling(castling) => {
+                write!(f, "'{}' are not valid castling rights", castling)
+            }
+            FenError::EnPassant(square) => {
+                write!(f, "'{}' is not a valid en passant square", square)
+            }
+            FenError::Clock(clock) => write!(f, "'{}' is not a valid move clock", clock),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+/// A position in Forsyth-Edwards Notation. Squares are indexed like on the board,
+/// starting from the first file of the positive side's back rank. Castling rights
+/// are kept as the squares of the rooks that may still castle.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Fen {
+    pub row_size: usize,
+    pub placement: Vec<isize>,
+    pub side_to_move: Sign,
+    pub castling: Vec<usize>,
+    pub en_passant: Option<usize>,
+    pub halfmove_clock: usize,
+    pub fullmove_number: usize,
+}
+
+impl Fen {
+    pub fn parse(fen: &str, row_size: usize, col_size: usize) -> Result<Self, FenError> {
+        let mut fields = fen.split_whitespace();
+        let placement = Self::parse_placement(
+            fields.next().ok_or(FenError::MissingField("placement"))?,
+            row_size,
+            col_size,
+        )?;
+        let side_to_move = match fields
+            .next()
+            .ok_or(FenError::MissingField("side to move"))?
+        {
+            "w" => Sign::Positive,
+            "b" => Sign::Negative,
+            side => return Err(FenError::SideToMove(side.to_string())),
+        };
+        let mut parsed = Fen {
+            row_size,
+            placement,
+            side_to_move,
+            castling: Vec::new(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+        };
+        let castling = fields.next().ok_or(FenError::MissingField("castling"))?;
+        parsed.castling = parsed.parse_castling(castling)?;
+        parsed.en_passant = match fields.next().ok_or(FenError::MissingField("en passant"))? {
+            "-" => None,
+            square => Some(
+                Square::parse_index(square, row_size, col_size)
+                    .ok_or_else(|| FenError::EnPassant(square.to_string()))?,
+            ),
+        };
+        if let Some(clock) = fields.next() {
+            parsed.halfmove_clock = clock
+                .parse()
+                .map_err(|_| FenError::Clock(clock.to_string()))?;
+        }
+        if let Some(clock) = fields.next() {
+            parsed.fullmove_number = clock
+                .parse()
+                .map_err(|_| FenError::Clock(clock.to_string()))?;
+        }
+        Ok(parsed)
+    }
+
+    fn parse_placement(
+        placement: &str,
+        row_size: usize,
+        col_size: usize,
+    ) -> Result<Vec<isize>, FenError> {
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != col_size {
+            return Err(FenError::RankCount(ranks.len()));
+        }
+        let mut state = vec![0; row_size * col_size];
+        for (r, rank) in ranks.iter().enumerate() {
+            let y = col_size - 1 - r;
+            let mut x = 0;
+            let mut empty: usize = 0;
+            for symbol in rank.chars() {
+                if let Some(digit) = symbol.to_digit(10) {
+                    let length = empty
+                        .checked_mul(10)
+                        .and_then(|empty| empty.checked_add(digit as usize))
+                        .and_then(|empty| empty.checked_add(x));
+                    match length {
+                        Some(length) if length <= row_size => empty = length - x,
+                        _ => return Err(FenError::RankLength(y + 1, length.unwrap_or(usize::MAX))),
+                    }
+                    continue;
+                }
+                x += empty;
+                empty = 0;
+                let piece =
+                    StandardPiece::from_symbol(symbol).ok_or(FenError::UnknownPiece(symbol))?;
+                if x >= row_size {
+                    return Err(FenError::RankLength(y + 1, x + 1));
+                }
+                let sign = if symbol.is_ascii_uppercase() {
+                    Sign::Positive
+                } else {
+                    Sign::Negative
+                };
+                state[x + y * row_size] = isize::from(piece) * sign;
+                x += 1;
+            }
+            x += empty;
+            if x != row_size {
+                return Err(FenError::RankLength(y + 1, x));
+            }
+        }
+        Ok(state)
+    }
+
+    fn parse_castling(&self, castling: &str) -> Result<Vec<usize>, FenError> {
+        let mut rooks = Vec::new();
+        if castling == "-" {
+            return Ok(rooks);
+        }
+        for symbol in castling.chars() {
+            let sign = if symbol.is_ascii_uppercase() {
+                Sign::Positive
+            } else {
+                Sign::Negative
+            };
+            let error = || FenError::Castling(castling.to_string());
+            let king_x = self.king_file(sign).ok_or_else(error)?;
+            let files: Vec<usize> = match symbol.to_ascii_lowercase() {
+                'k' => (king_x + 1..self.row_size).rev().collect(),
+                'q' => (0..king_x).collect(),
+                file @ 'a'..='z' => match Square::parse_file(&file.to_string()) {
+                    Some(x) if x < self.row_size => vec![x],
+                    _ => return Err(error()),
+                },
+                _ => return Err(error()),
+            };
+            let rook = files
+                .into_iter()
+                .map(|x| x + self.back_rank(sign) * self.row_size)
+                .find(|u| self.placement[*u] == isize::from(StandardPiece::Rook) * sign)
+                .ok_or_else(error)?;
+            rooks.push(rook);
+        }
+        Ok(rooks)
+    }
+
+    fn back_rank(&self, sign: Sign) -> usize {
+        match sign {
+            Sign::Negative => self.placement.len() / self.row_size - 1,
+            _ => 0,
+        }
+    }
+
+    fn king_file(&self, sign: Sign) -> Option<usize> {
+        let offset = self.back_rank(sign) * self.row_size;
+        (0..self.row_size)
+            .find(|x| self.placement[x + offset] == isize::from(StandardPiece::King) * sign)
+    }
+
+    /// The castling rights in X-FEN, which writes them like standard FEN unless a
+    /// rook other than the outermost one on its side of the king may castle, as in
+    /// Chess960. That rook is named by its file, like in Shredder-FEN, unless the
+    /// file has no single letter, past the 26th.
+    fn format_castling(&self) -> String {
+        let mut castling = String::new();
+        for sign in [Sign::Positive, Sign::Negative] {
+            let king_x = match self.king_file(sign) {
+                Some(x) => x,
+                None => continue,
+            };
+            let offset = self.back_rank(sign) * self.row_size;
+            let rook = isize::from(StandardPiece::Rook) * sign;
+            let mut files: Vec<usize> = self
+                .castling
+                .iter()
+                .filter(|u| (offset..offset + self.row_size).contains(*u))
+                .map(|u| u - offset)
+                .collect();
+            files.sort_unstable_by(|a, b| b.cmp(a));
+            for x in files {
+                let (symbol, outside) = match x > king_x {
+                    true => ('K', x + 1..self.row_size),
+                    false => ('Q', 0..x),
+                };
+                let inner = outside
+                    .into_iter()
+                    .any(|o| self.placement[o + offset] == rook);
+                let symbol = match inner && x < 26 {
+                    true => Square::file_name(x).to_ascii_uppercase(),
+                    false => symbol.to_string(),
+                };
+                castling.push_str(&match sign {
+                    Sign::Negative => symbol.to_ascii_lowercase(),
+                    _ => symbol,
+                });
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+        castling
+    }
+}
+
+impl std::fmt::Display for Fen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let col_size = self.placement.len() / self.row_size;
+        let mut ranks = Vec::new();
+        for y in (0..col_size).rev() {
+            let mut rank = String::new();
+            let mut empty = 0;
+            for x in 0..self.row_size {
+                let i = self.placement[x + y * self.row_size];
+                if i == 0 {
+                    empty += 1;
+                    continue;
+                }
+                if empty > 0 {
+                    rank.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                let symbol = StandardPiece::from(i).symbol();
+                rank.push(match Sign::from(i) {
+                    Sign::Positive => symbol.to_ascii_uppercase(),
+                    _ => symbol,
+                });
+            }
+            if empty > 0 {
+                rank.push_str(&empty.to_string());
+            }
+            ranks.push(rank);
+        }
+        let side_to_move = match self.side_to_move {
+            Sign::Negative => "b",
+            _ => "w",
+        };
+        let en_passant = match self.en_passant {
+            Some(u) => Square::from_index(u, self.row_size).to_string(),
+            None => String::from("-"),
+        };
+        write!(
+            f,
+            "{} {} {} {} {} {}",
+            ranks.join("/"),
+            side_to_move,
+            self.format_castling(),
+            en_passant,
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fen, FenError, STARTING_FEN};
+
+    fn parse(fen: &str) -> Result<Fen, FenError> {
+        Fen::parse(fen, 8, 8)
+    }
+
+    #[test]
+    fn round_trip() {
+        for fen in [
+            STARTING_FEN,
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2",
+            "r3k2r/8/8/8/3pP3/8/8/R3K2R b Kq e3 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w - - 12 40",
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9",
+        ] {
+            assert_eq!(parse(fen).unwrap().to_string(), fen);
+        }
+    }
+
+    #[test]
+    fn castling_rights_are_rook_squares() {
+        let fen = parse("r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1").unwrap();
+        assert_eq!(fen.castling, vec![7, 56]);
+        let shredder = parse("r3k2r/8/8/8/8/8/8/R3K2R w HAha - 0 1").unwrap();
+        assert_eq!(shredder.castling, vec![7, 0, 63, 56]);
+        assert!(shredder.to_string().contains(" w KQkq - "));
+    }
+
+    #[test]
+    fn errors() {
+        let cases = [
+            ("", FenError::MissingField("placement")),
+            ("8/8/8/8/8/8/8/8", FenError::MissingField("side to move")),
+            ("8/8/8/8/8/8/8/8 w", FenError::MissingField("castling")),
+            ("8/8/8/8/8/8/8/8 w -", FenError::MissingField("en passant")),
+            ("8/8/8/8/8/8/8 w - -", FenError::RankCount(7)),
+            ("9/8/8/8/8/8/8/8 w - -", FenError::RankLength(8, 9)),
+            ("7/8/8/8/8/8/8/8 w - -", FenError::RankLength(8, 7)),
+            ("ppppppppp/8/8/8/8/8/8/8 w - -", FenError::RankLength(8, 9)),
+            (
+                "99999999999999999999999/8/8/8/8/8/8/8 w - - 0 1",
+                FenError::RankLength(8, 9),
+            ),
+            ("x7/8/8/8/8/8/8/8 w - -", FenError::UnknownPiece('x')),
+            (
+                "8/8/8/8/8/8/8/8 x - -",
+                FenError::SideToMove("x".to_string()),
+            ),
+            ("8/8/8/8/8/8/8/8 w K -", FenError::Castling("K".to_string())),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w Z -",
+                FenError::Castling("Z".to_string()),
+            ),
+            (
+                "8/8/8/8/8/8/8/8 w - e9",
+                FenError::EnPassant("e9".to_string()),
+            ),
+            ("8/8/8/8/8/8/8/8 w - - x", FenError::Clock("x".to_string())),
+            (
+                "8/8/8/8/8/8/8/8 w - - 0 -1",
+                FenError::Clock("-1".to_string()),
+            ),
+        ];
+        for (fen, error) in cases {
+            assert_eq!(parse(fen), Err(error), "{}", fen);
+        }
+    }
+}
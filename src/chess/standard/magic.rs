@@ -0,0 +1,283 @@
+// Magic bitboards for the attacks of sliding pieces. The squares that can block a
+// bishop or rook on a given square are masked out of the occupied squares and
+// multiplied by a magic number, which maps every arrangement of blockers to its own
+// slot of a table holding the attacked squares. The tables are filled the first
+// time they are needed.
+
+use std::sync::OnceLock;
+
+use crate::chess::standard::bitboard::{sliding_attacks, DIAGONALS, STRAIGHTS};
+
+// Magic numbers found by trying random numbers with few bits set until every
+// arrangement of blockers lands in a slot holding the right attacks, using as many
+// bits of the index as there are squares that can block.
+const BISHOP_MAGICS: [u64; 64] = [
+    0x0060_0404_1084_0210,
+    0x01a0_c202_0200_2a00,
+    0x0004_045c_8a00_0331,
+    0x4488_4481_0010_0261,
+    0x0001_1040_0040_0500,
+    0x0212_880d_4003_4202,
+    0x8001_0088_04c0_0001,
+    0x0142_9148_0414_2000,
+    0x9400_1010_8169_0400,
+    0x0000_0202_1414_0090,
+    0x2000_0914_4102_0000,
+    0x0000_820a_0208_4000,
+    0x001c_1450_4002_2006,
+    0x2000_0422_2010_0880,
+    0x1002_0048_0a08_6010,
+    0x2040_0041_0088_a000,
+    0x0120_0010_0202_4820,
+    0x2112_1820_420c_2110,
+    0x4040_4008_0081_2181,
+    0x9018_0200_8281_0060,
+    0x0104_0002_030c_0000,
+    0x0c00_c041_8060_2000,
+    0x060c_8002_10c4_2023,
+    0x060a_0684_ad05_0804,
+    0x0020_0420_a084_4409,
+    0x4010_1045_4802_0080,
+    0x0040_2400_0800_4408,
+    0x2804_0100_0020_0880,
+    0x0025_0100_4010_4001,
+    0x4000_4e02_0901_0100,
+    0x8301_0201_0108_0101,
+    0x8200_4082_0104_0100,
+    0x1048_2308_3040_0800,
+    0x0001_0421_0030_0920,
+    0x0041_c020_8050_0103,
+    0xe840_0808_0022_0a00,
+    0x0081_0104_00c2_0020,
+    0x0082_1004_4102_0800,
+    0x0018_0610_6180_8804,
+    0x0080_9a00_5040_8400,
+    0x0502_9010_5280_0800,
+    0x0400_4808_0901_0400,
+    0x0008_a068_2800_1002,
+    0x8440_0620_1800_010a,
+    0x4000_100a_1013_4200,
+    0x0020_0400_8026_0200,
+    0x8008_4810_808c_0400,
+    0x809c_4802_00e0_8854,
+    0x4008_4804_0421_0000,
+    0x1100_2202_1220_2428,
+    0x2028_0084_00a2_0300,
+    0x021c_0040_8404_1000,
+    0x0030_0130_202a_0000,
+    0x8000_4208_9101_0000,
+    0xe008_4214_0822_0020,
+    0x0828_10a4_0040_4400,
+    0x8440_4048_00b0_1000,
+    0x0004_008c_0c02_0280,
+    0x0900_c421_0041_1050,
+    0x0806_2201_8942_0220,
+    0x0702_0021_1020_2210,
+    0x5104_20a1_0202_0200,
+    0x3044_0410_4202_1400,
+    0x2021_0802_0440_4204,
+];
+
+const ROOK_MAGICS: [u64; 64] = [
+    0xa080_0018_2040_0080,
+    0x0040_0020_0040_1000,
+    0x0180_3001_6000_8008,
+    0x0480_0408_0080_1001,
+    0x2a00_0810_8420_4200,
+    0x0480_0180_1200_3400,
+    0x0600_0100_8200_0428,
+    0x4200_0225_0c01_8042,
+    0x0040_8000_4000_2080,
+    0x0122_0020_4201_008c,
+    0x2002_0040_2200_1080,
+    0x0026_0022_0040_0810,
+    0x2000_8080_0800_0400,
+    0x0022_0002_0088_3104,
+    0x2c88_8080_0100_0200,
+    0x1112_0000_8042_0104,
+    0x0100_9080_0040_0020,
+    0x0080_8080_2000_4000,
+    0x0008_4100_1020_0300,
+    0x0014_8080_1000_0801,
+    0x0080_0500_1100_4800,
+    0x00d1_0100_0208_0400,
+    0xa080_0400_0a30_0158,
+    0x1000_1200_0528_8244,
+    0x020c_4000_8024_8002,
+    0x4020_4112_0022_0082,
+    0x0041_0841_0010_2004,
+    0x0008_0021_0100_1000,
+    0x1010_5005_0008_0100,
+    0x0500_4008_0110_0420,
+    0x1402_0042_0039_0408,
+    0x0401_0001_0000_4082,
+    0x0380_c000_8280_0022,
+    0x0010_0020_0040_4000,
+    0x4420_0020_8180_5000,
+    0x0088_0008_8080_1000,
+    0x0008_0004_0080_0882,
+    0x8042_0010_0200_0408,
+    0x0000_1002_0400_0188,
+    0x3804_8000_4080_0100,
+    0x0400_4000_8001_8020,
+    0x6050_0020_0048_4000,
+    0x9240_4100_2001_0018,
+    0x0110_0400_0800_4040,
+    0x0000_0800_0501_0010,
+    0x0002_0010_8812_0044,
+    0x0008_1002_0804_0001,
+    0x0001_0000_8045_002a,
+    0x0001_0020_4080_0100,
+    0x1602_2092_0049_0200,
+    0x1109_1000_2000_8880,
+    0x5000_1001_0020_0900,
+    0x0000_0400_8008_0080,
+    0x0003_0002_0400_0900,
+    0x4220_0806_3003_5400,
+    0x6140_8011_0000_6080,
+    0x1009_2341_0080_0039,
+    0x8000_2012_0080_4102,
+    0x5004_1008_2200_4082,
+    0x2802_0004_4010_0822,
+    0x0801_0084_0800_1017,
+    0x0002_0001_0804_1062,
+    0x8040_1211_0812_9044,
+    0x0400_0324_1100_8242,
+];
+
+static TABLES: OnceLock<Tables> = OnceLock::new();
+
+#[derive(Clone, Copy, Default)]
+struct Magic {
+    /// The squares whose occupancy changes the attacks from the square.
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    /// Where the attacks from the square start in `Tables::attacks`.
+    offset: usize,
+}
+
+impl Magic {
+    fn index(&self, occupied: u64) -> usize {
+        self.offset + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+struct Tables {
+    bishops: [Magic; 64],
+    rooks: [Magic; 64],
+    attacks: Vec<u64>,
+}
+
+/// The squares a bishop on `square` attacks, up to and including the first
+/// `occupied` square in every direction.
+pub fn bishop_attacks(square: usize, occupied: u64) -> u64 {
+    let tables = tables();
+    tables.attacks[tables.bishops[square].index(occupied)]
+}
+
+/// The squares a rook on `square` attacks, up to and including the first
+/// `occupied` square in every direction.
+pub fn rook_attacks(square: usize, occupied: u64) -> u64 {
+    let tables = tables();
+    tables.attacks[tables.rooks[square].index(occupied)]
+}
+
+pub fn queen_attacks(square: usize, occupied: u64) -> u64 {
+    bishop_attacks(square, occupied) | rook_attacks(square, occupied)
+}
+
+/// Fills the tables ahead of the first lookup, which would otherwise pay for it.
+pub fn init() {
+    tables();
+}
+
+fn tables() -> &'static Tables {
+    TABLES.get_or_init(|| {
+        let mut tables = Tables {
+            bishops: [Magic::default(); 64],
+            rooks: [Magic::default(); 64],
+            attacks: Vec::new(),
+        };
+        for square in 0..64 {
+            tables.bishops[square] = fill(
+                square,
+                BISHOP_MAGICS[square],
+                &DIAGONALS,
+                &mut tables.attacks,
+            );
+            tables.rooks[square] =
+                fill(square, ROOK_MAGICS[square], &STRAIGHTS, &mut tables.attacks);
+        }
+        tables
+    })
+}
+
+/// The squares a piece on `square` sliding in `directions` passes before the edge
+/// of the board. A piece on the last square of a direction blocks nothing.
+fn relevant(square: usize, directions: &[(isize, isize)]) -> u64 {
+    let mut mask = 0;
+    for (dx, dy) in directions {
+        let (mut x, mut y) = ((square % 8) as isize + dx, (square / 8) as isize + dy);
+        while (0..8).contains(&(x + dx)) && (0..8).contains(&(y + dy)) {
+            mask |= 1 << (x + y * 8);
+            x += dx;
+            y += dy;
+        }
+    }
+    mask
+}
+
+/// Appends to `attacks` the slots of a piece on `square` sliding in `directions`,
+/// with the attacks for every arrangement of blockers in the slot `magic` maps it to.
+fn fill(square: usize, magic: u64, directions: &[(isize, isize)], attacks: &mut Vec<u64>) -> Magic {
+    let mask = relevant(square, directions);
+    let bits = mask.count_ones();
+    let magic = Magic {
+        mask,
+        magic,
+        shift: 64 - bits,
+        offset: attacks.len(),
+    };
+    attacks.resize(attacks.len() + (1 << bits), 0);
+    let mut blockers: u64 = 0;
+    loop {
+        let i = magic.index(blockers);
+        let attacked = sliding_attacks(square, blockers, directions);
+        debug_assert!(
+            attacks[i] == 0 || attacks[i] == attacked,
+            "bad magic on {square}"
+        );
+        attacks[i] = attacked;
+        blockers = blockers.wrapping_sub(mask) & mask;
+        if blockers == 0 {
+            return magic;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookups_match_walking_the_board() {
+        let mut occupied: u64 = 0x0123_4567_89ab_cdef;
+        for _ in 0..1000 {
+            occupied ^= occupied << 13;
+            occupied ^= occupied >> 7;
+            occupied ^= occupied << 17;
+            let sparse = occupied & occupied.rotate_left(23);
+            for square in 0..64 {
+                assert_eq!(
+                    bishop_attacks(square, sparse),
+                    sliding_attacks(square, sparse, &DIAGONALS)
+                );
+                assert_eq!(
+                    rook_attacks(square, sparse),
+                    sliding_attacks(square, sparse, &STRAIGHTS)
+                );
+            }
+        }
+    }
+}
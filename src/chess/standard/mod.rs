@@ -1,2 +1,23 @@
+use crate::chess::{
+    game::Game,
+    standard::{
+        bitboard::BitBoard,
+        board::StandardBoard,
+        piece::{StandardPiece, StandardPieceSet},
+    },
+};
+
+pub mod bitboard;
 pub mod board;
+pub mod chess960;
+pub mod eval;
+pub mod fen;
+pub mod magic;
+pub mod pgn;
 pub mod piece;
+
+/// A game of chess on the standard eight by eight board.
+pub type StandardGame = Game<StandardBoard<8, 8, 64, StandardPiece>, StandardPieceSet>;
+
+/// A game of chess on a `BitBoard`, which plays out exactly like a `StandardGame`.
+pub type BitBoardGame = Game<BitBoard, StandardPieceSet>;
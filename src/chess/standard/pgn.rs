@@ -0,0 +1,570 @@
+use std::{iter::Peekable, str::Chars};
+
+use crate::chess::{
+    game::GameResult,
+    piece::Sign,
+    square::Square,
+    standard::{fen::FenError, piece::StandardPiece},
+};
+
+pub const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+const LINE_LENGTH: usize = 80;
+
+#[derive(Debug, PartialEq)]
+pub enum PgnError {
+    Tag(String),
+    UnterminatedComment,
+    UnbalancedVariation,
+    InvalidSan(String),
+    IllegalMove(String),
+    AmbiguousMove(String),
+    Fen(FenError),
+}
+
+impl std::fmt::Display for PgnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PgnError::Tag(tag) => write!(f, "'{}' is not a valid tag pair", tag),
+            PgnError::UnterminatedComment => write!(f, "a comment is never closed"),
+            PgnError::UnbalancedVariation => write!(f, "the variations are not balanced"),
+            PgnError::InvalidSan(san) => write!(f, "'{}' is not a valid SAN move", san),
+            PgnError::IllegalMove(san) => write!(f, "'{}' is not a legal move", san),
+            PgnError::AmbiguousMove(san) => write!(f, "'{}' matches several moves", san),
+            PgnError::Fen(error) => write!(f, "the FEN tag is invalid: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+impl From<FenError> for PgnError {
+    fn from(error: FenError) -> Self {
+        PgnError::Fen(error)
+    }
+}
+
+/// A single game in Portable Game Notation. Moves are kept in Standard Algebraic
+/// Notation, while comments, variations and annotation glyphs are dropped when
+/// parsing. The game termination is the value of the `Result` tag.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Pgn {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<String>,
+}
+
+impl Pgn {
+    /// An empty game with the Seven Tag Roster filled with unknown values.
+    pub fn new() -> Self {
+        let tags = SEVEN_TAG_ROSTER
+            .iter()
+            .map(|name| {
+                let value = match *name {
+                    "Date" => "????.??.??",
+                    "Result" => "*",
+                    _ => "?",
+                };
+                (name.to_string(), value.to_string())
+            })
+            .collect();
+        Pgn {
+            tags,
+            moves: Vec::new(),
+        }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old)) => *old = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn result(&self) -> &str {
+        self.tag("Result").unwrap_or("*")
+    }
+
+    /// Parses every game of `text`, such as a database exported by another program.
+    pub fn parse_all(text: &str) -> Result<Vec<Self>, PgnError> {
+        let mut parser = Parser(text.chars().peekable());
+        let mut games = Vec::new();
+        while let Some(game) = parser.game()? {
+            games.push(game);
+        }
+        Ok(games)
+    }
+
+    /// The move number and side to move of the first move, taken from the `FEN` tag.
+    fn first_move(&self) -> (usize, Sign) {
+        let mut fields = self
+            .tag("FEN")
+            .unwrap_or_default()
+            .split_whitespace()
+            .skip(1);
+        let side = match fields.next() {
+            Some("b") => Sign::Negative,
+            _ => Sign::Positive,
+        };
+        let number = fields.nth(3).and_then(|n| n.parse().ok()).unwrap_or(1);
+        (number, side)
+    }
+}
+
+impl Default for Pgn {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for Pgn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, value) in &self.tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{} \"{}\"]", name, value)?;
+        }
+        writeln!(f)?;
+        let (mut number, mut side) = self.first_move();
+        let mut tokens = Vec::new();
+        for (i, san) in self.moves.iter().enumerate() {
+            match side {
+                Sign::Negative if i == 0 => tokens.push(format!("{}...", number)),
+                Sign::Negative => (),
+                _ => tokens.push(format!("{}.", number)),
+            }
+            tokens.push(san.clone());
+            if side == Sign::Negative {
+                number += 1;
+            }
+            side = -side;
+        }
+        tokens.push(self.result().to_string());
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > LINE_LENGTH {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        writeln!(f, "{}", line)
+    }
+}
+
+/// The value of the `Result` tag for the state of a game.
+pub fn result_tag(result: GameResult) -> &'static str {
+    match result {
+        GameResult::Ongoing => "*",
+        GameResult::Checkmate(Sign::Positive) | GameResult::Resignation(Sign::Positive) => "1-0",
+        GameResult::Checkmate(_) | GameResult::Resignation(_) => "0-1",
+        GameResult::Stalemate | GameResult::Draw(_) => "1/2-1/2",
+    }
+}
+
+struct Parser<'a>(Peekable<Chars<'a>>);
+
+impl<'a> Parser<'a> {
+    /// Reads the next game, which ends with its termination marker or where the
+    /// tags of the following game begin.
+    fn game(&mut self) -> Result<Option<Pgn>, PgnError> {
+        let mut pgn = Pgn {
+            tags: Vec::new(),
+            moves: Vec::new(),
+        };
+        let mut found = false;
+        let mut depth = 0;
+        while let Some(&c) = self.0.peek() {
+            match c {
+                c if c.is_whitespace() => {
+                    self.0.next();
+                }
+                '[' if depth == 0 => {
+                    if !pgn.moves.is_empty() {
+                        break;
+                    }
+                    self.0.next();
+                    let (name, value) = self.tag()?;
+                    pgn.set_tag(&name, &value);
+                    found = true;
+                }
+                '{' => {
+                    self.0.next();
+                    if !self.0.by_ref().any(|c| c == '}') {
+                        return Err(PgnError::UnterminatedComment);
+                    }
+                }
+                ';' | '%' => {
+                    self.0.by_ref().find(|c| *c == '\n');
+                }
+                '(' => {
+                    self.0.next();
+                    depth += 1;
+                }
+                ')' => {
+                    self.0.next();
+                    if depth == 0 {
+                        return Err(PgnError::UnbalancedVariation);
+                    }
+                    depth -= 1;
+                }
+                _ => {
+                    let word = self.word();
+                    if word.is_empty() {
+                        self.0.next();
+                        continue;
+                    }
+                    found = true;
+                    if depth > 0 {
+                        continue;
+                    }
+                    if RESULTS.contains(&word.as_str()) {
+                        pgn.set_tag("Result", &word);
+                        return Ok(Some(pgn));
+                    }
+                    if let Some(san) = Self::san_token(&word) {
+                        pgn.moves.push(san.to_string());
+                    }
+                }
+            }
+        }
+        if depth > 0 {
+            return Err(PgnError::UnbalancedVariation);
+        }
+        Ok(found.then_some(pgn))
+    }
+
+    /// Reads a tag pair after its opening bracket.
+    fn tag(&mut self) -> Result<(String, String), PgnError> {
+        let name: String = self.0.by_ref().take_while(|c| *c != '"').collect();
+        let mut value = String::new();
+        let mut closed = false;
+        while let Some(c) = self.0.next() {
+            match c {
+                '\\' => value.extend(self.0.next()),
+                '"' => {
+                    closed = true;
+                    break;
+                }
+                c => value.push(c),
+            }
+        }
+        let name = name.trim().to_string();
+        let valid = closed
+            && !name.is_empty()
+            && !name.contains(char::is_whitespace)
+            && self.0.by_ref().find(|c| !c.is_whitespace()) == Some(']');
+        if !valid {
+            return Err(PgnError::Tag(name));
+        }
+        Ok((name, value))
+    }
+
+    fn word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(&c) = self.0.peek() {
+            if c.is_whitespace() || "{}();[".contains(c) {
+                break;
+            }
+            word.push(c);
+            self.0.next();
+        }
+        word
+    }
+
+    /// Strips move numbers and annotations from a movetext word, returning the move
+    /// it contains if any.
+    fn san_token(word: &str) -> Option<&str> {
+        let mut san = word;
+        if san.starts_with(|c: char| c.is_ascii_digit()) {
+            match san.rfind('.') {
+                Some(dot) => san = &san[dot + 1..],
+                None if san.chars().all(|c| c.is_ascii_digit()) => return None,
+                None => (),
+            }
+        }
+        if san.starts_with('$') {
+            return None;
+        }
+        let san = san.trim_end_matches(['!', '?']);
+        if san.is_empty() {
+            return None;
+        }
+        Some(san)
+    }
+}
+
+/// A move in Standard Algebraic Notation, as far as it can be understood without
+/// the position it is played in.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum San {
+    Castle {
+        kingside: bool,
+    },
+    Move {
+        piece: StandardPiece,
+        file: Option<usize>,
+        rank: Option<usize>,
+        capture: bool,
+        to: usize,
+        promotion: Option<StandardPiece>,
+    },
+}
+
+impl San {
+    pub fn parse(san: &str, row_size: usize, col_size: usize) -> Result<Self, PgnError> {
+        let error = || PgnError::InvalidSan(san.to_string());
+        let text = san.trim_end_matches(['+', '#', '!', '?']);
+        match text {
+            "O-O" | "0-0" => return Ok(San::Castle { kingside: true }),
+            "O-O-O" | "0-0-0" => return Ok(San::Castle { kingside: false }),
+            _ => (),
+        }
+        let (text, promotion) = match text.split_once('=') {
+            Some((text, promotion)) => {
+                let mut chars = promotion.chars();
+                match (
+                    chars.next().and_then(StandardPiece::from_symbol),
+                    chars.next(),
+                ) {
+                    (Some(piece), None) => (text, Some(piece)),
+                    _ => return Err(error()),
+                }
+            }
+            None => match text.chars().last() {
+                Some(last) if last.is_ascii_uppercase() => (
+                    &text[..text.len() - 1],
+                    Some(StandardPiece::from_symbol(last).ok_or_else(error)?),
+                ),
+                _ => (text, None),
+            },
+        };
+        let (piece, text) = match text.chars().next() {
+            Some(first) if first.is_ascii_uppercase() => (
+                StandardPiece::from_symbol(first).ok_or_else(error)?,
+                &text[1..],
+            ),
+            _ => (StandardPiece::Pawn, text),
+        };
+        let split = text
+            .rfind(|c: char| c.is_ascii_lowercase() && c != 'x')
+            .ok_or_else(error)?;
+        let to = Square::parse_index(&text[split..], row_size, col_size).ok_or_else(error)?;
+        let prefix = &text[..split];
+        let capture = prefix.ends_with('x');
+        let prefix = prefix.strip_suffix('x').unwrap_or(prefix);
+        let letters = prefix
+            .find(|c: char| !c.is_ascii_lowercase())
+            .unwrap_or(prefix.len());
+        let (file, rank) = match letters {
+            0 => (None, prefix),
+            _ => (
+                Some(Square::parse_file(&prefix[..letters]).ok_or_else(error)?),
+                &prefix[letters..],
+            ),
+        };
+        let rank = match rank {
+            "" => None,
+            rank => Some(
+                rank.parse::<usize>()
+                    .ok()
+                    .and_then(|rank| rank.checked_sub(1))
+                    .ok_or_else(error)?,
+            ),
+        };
+        Ok(San::Move {
+            piece,
+            file,
+            rank,
+            capture,
+            to,
+            promotion,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pgn, PgnError, San};
+    use crate::chess::{
+        square::AlgebraicMove,
+        standard::{fen::STARTING_FEN, piece::StandardPiece, StandardGame},
+    };
+
+    fn parse(san: &str) -> Result<San, PgnError> {
+        San::parse(san, 8, 8)
+    }
+
+    #[test]
+    fn san_moves() {
+        assert_eq!(parse("O-O+"), Ok(San::Castle { kingside: true }));
+        assert_eq!(parse("0-0-0"), Ok(San::Castle { kingside: false }));
+        assert_eq!(
+            parse("e4"),
+            Ok(San::Move {
+                piece: StandardPiece::Pawn,
+                file: None,
+                rank: None,
+                capture: false,
+                to: 28,
+                promotion: None,
+            })
+        );
+        assert_eq!(
+            parse("exd8=Q#"),
+            Ok(San::Move {
+                piece: StandardPiece::Pawn,
+                file: Some(4),
+                rank: None,
+                capture: true,
+                to: 59,
+                promotion: Some(StandardPiece::Queen),
+            })
+        );
+        assert_eq!(parse("b1N"), parse("b1=N"));
+        assert_eq!(
+            parse("Nbxd7!?"),
+            Ok(San::Move {
+                piece: StandardPiece::Knight,
+                file: Some(1),
+                rank: None,
+                capture: true,
+                to: 51,
+                promotion: None,
+            })
+        );
+        assert_eq!(
+            parse("Qh4e1"),
+            Ok(San::Move {
+                piece: StandardPiece::Queen,
+                file: Some(7),
+                rank: Some(3),
+                capture: false,
+                to: 4,
+                promotion: None,
+            })
+        );
+        assert_eq!(
+            San::parse("Raab1", 30, 8),
+            Ok(San::Move {
+                piece: StandardPiece::Rook,
+                file: Some(26),
+                rank: None,
+                capture: false,
+                to: 1,
+                promotion: None,
+            })
+        );
+        for san in ["", "Z4", "e9", "i1", "Nx", "e8=", "e8=X", "e8=QQ", "N1x"] {
+            assert_eq!(
+                parse(san),
+                Err(PgnError::InvalidSan(san.to_string())),
+                "{}",
+                san
+            );
+        }
+    }
+
+    #[test]
+    fn movetext_skips_everything_but_moves() {
+        let text = r#"[Event "Casual \"game\""]
+[White "A"]
+
+1. e4 {best by test} e5 2. Nf3!? (2. f4 exf4 (2... d5) 3. Nf3) 2... Nc6 $1
+; a comment to the end of the line 3. Bb5
+% an escaped line
+3. Bc4 Bc5?? 1-0
+
+[Event "Next"]
+1. d4 *
+"#;
+        let games = Pgn::parse_all(text).unwrap();
+        assert_eq!(games.len(), 2);
+        let pgn = &games[0];
+        assert_eq!(pgn.tag("Event"), Some(r#"Casual "game""#));
+        assert_eq!(pgn.tag("White"), Some("A"));
+        assert_eq!(pgn.moves, ["e4", "e5", "Nf3", "Nc6", "Bc4", "Bc5"]);
+        assert_eq!(pgn.result(), "1-0");
+        assert_eq!(games[1].tag("Event"), Some("Next"));
+        assert_eq!(games[1].moves, ["d4"]);
+        assert_eq!(
+            Pgn::parse_all("1. e4 { never closed"),
+            Err(PgnError::UnterminatedComment)
+        );
+        assert_eq!(
+            Pgn::parse_all("1. e4 (1. d4"),
+            Err(PgnError::UnbalancedVariation)
+        );
+        assert_eq!(
+            Pgn::parse_all("1. e4 e5)"),
+            Err(PgnError::UnbalancedVariation)
+        );
+        assert_eq!(
+            Pgn::parse_all("[Event \"unclosed]"),
+            Err(PgnError::Tag("Event".to_string()))
+        );
+    }
+
+    /// The SAN of the legal move `text`, given in long algebraic notation, in `fen`.
+    fn san_of(fen: &str, text: &str) -> String {
+        let mut game = StandardGame::from_fen(fen).unwrap();
+        let algebraic: AlgebraicMove = text.parse().unwrap();
+        let piece = game.piece_at(algebraic.from);
+        let (id, target) = game
+            .legal_moves(game.side_to_move())
+            .into_iter()
+            .find(|(id, target)| Some(*id) == piece && target.u() == algebraic.to.index(8))
+            .unwrap();
+        let promotion = algebraic.promotion.and_then(StandardPiece::from_symbol);
+        game.san(&id, &target, promotion)
+    }
+
+    #[test]
+    fn san_of_moves() {
+        let knights = "7k/8/8/8/8/8/5N1N/4K3 w - - 0 1";
+        assert_eq!(san_of(knights, "f2g4"), "Nfg4");
+        assert_eq!(san_of(knights, "h2g4"), "Nhg4");
+        assert_eq!(san_of(knights, "f2d3"), "Nd3");
+        let rooks = "7k/8/R7/8/8/8/8/R3K3 w - - 0 1";
+        assert_eq!(san_of(rooks, "a6a3"), "R6a3");
+        assert_eq!(san_of(rooks, "a1a3"), "R1a3");
+        let queens = "7k/8/8/8/Q1Q5/8/2Q5/4K3 w - - 0 1";
+        assert_eq!(san_of(queens, "a4b3"), "Qab3");
+        assert_eq!(san_of(queens, "c4b3"), "Qc4b3");
+        assert_eq!(san_of(queens, "c2b3"), "Q2b3");
+
+        let promotion = "1n5k/P7/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(san_of(promotion, "a7a8q"), "a8=Q");
+        assert_eq!(san_of(promotion, "a7b8q"), "axb8=Q+");
+        assert_eq!(san_of(promotion, "a7b8n"), "axb8=N");
+        assert_eq!(san_of("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"), "Ra8#");
+        assert_eq!(san_of("r3k3/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1"), "O-O");
+        assert_eq!(
+            san_of(
+                "rnbqkbnr/ppppp2p/8/5Pp1/8/8/PPPP1PPP/RNBQKBNR w KQkq g6 0 3",
+                "f5g6"
+            ),
+            "fxg6"
+        );
+
+        let mut game = StandardGame::from_fen(STARTING_FEN).unwrap();
+        for san in ["f3", "e5", "g4", "Qh4#"] {
+            game.play_san(san).unwrap();
+        }
+        assert_eq!(
+            game.export_pgn().to_string().lines().last(),
+            Some("1. f3 e5 2. g4 Qh4# 0-1")
+        );
+    }
+}
@@ -1,10 +1,11 @@
 use crate::chess::{
     board::{Board, BoardHistory, BoardSlice},
-    movement::{CanCapture, CanMove, Move},
-    piece::{Piece, PieceId, PieceSet},
+    movement::{CanCapture, CanMove, Move, MoveKind, Target},
+    piece::{Piece, PieceId, PiecePos, PieceSet, Sign},
+    square::Square,
 };
 
-#[derive(PartialEq, PartialOrd, Clone, Copy)]
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
 pub enum StandardPiece {
     None,
     Pawn,
@@ -50,16 +51,48 @@ impl Piece for StandardPiece {
     }
 }
 
+impl StandardPiece {
+    /// The lowercase letter naming the piece in FEN and algebraic notation.
+    pub fn symbol(&self) -> char {
+        match self {
+            StandardPiece::None => ' ',
+            StandardPiece::Pawn => 'p',
+            StandardPiece::Knight => 'n',
+            StandardPiece::Bishop => 'b',
+            StandardPiece::Rook => 'r',
+            StandardPiece::Queen => 'q',
+            StandardPiece::King => 'k',
+        }
+    }
+
+    pub fn from_symbol(symbol: char) -> Option<Self> {
+        match symbol.to_ascii_lowercase() {
+            'p' => Some(StandardPiece::Pawn),
+            'n' => Some(StandardPiece::Knight),
+            'b' => Some(StandardPiece::Bishop),
+            'r' => Some(StandardPiece::Rook),
+            'q' => Some(StandardPiece::Queen),
+            'k' => Some(StandardPiece::King),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
 pub struct StandardPieceSet;
 
 impl StandardPieceSet {
     const PAWN_MOVESET: [CanMove<'static, StandardPiece>; 6] = [
         CanMove::Free(Move::new(0, 1, 1), CanCapture::None),
-        CanMove::Conditional(&|id, _, history| {
-            if history.get_slice(id).is_none() {
-                return Some((Move::new(0, 1, 2), CanCapture::None));
-            }
-            return None;
+        CanMove::Conditional(&|id, board, _| {
+            board.get_pos(id).and_then(|op| {
+                let passed = op.top(id.sign());
+                if Self::rank(id.sign(), &op) == 1 && board.get_id_not_none(&passed).is_none() {
+                    let kind = MoveKind::DoubleStep(passed.u());
+                    return Some((Move::special(0, 2, 1, kind), CanCapture::None));
+                }
+                None
+            })
         }),
         CanMove::Conditional(&|id, board, _| {
             board.get_pos(id).and_then(|op| {
@@ -68,7 +101,7 @@ impl StandardPieceSet {
                     if other.opposes(id) {
                         return Some((Move::new(-1, 1, 1), CanCapture::Opposing(1)));
                     }
-                    return None;
+                    None
                 })
             })
         }),
@@ -79,7 +112,7 @@ impl StandardPieceSet {
                     if other.opposes(id) {
                         return Some((Move::new(1, 1, 1), CanCapture::Opposing(1)));
                     }
-                    return None;
+                    None
                 })
             })
         }),
@@ -87,18 +120,14 @@ impl StandardPieceSet {
             board.get_pos(id).and_then(|op| {
                 let op_left = op.left(id.sign());
                 board.get_id(&op_left).and_then(|other| {
-                    if other.opposes(id) && other.piece() == StandardPiece::Pawn {
-                        return history.get_slice(&other).and_then(|prev| {
-                            prev.inner().last().and_then(|last| {
-                                if last == &op_left.bottom(other.sign()).bottom(other.sign()).u() {
-                                    return Some((Move::new(-1, 1, 1), CanCapture::None));
-                                } else {
-                                    return None;
-                                }
-                            })
-                        });
+                    if other.opposes(id)
+                        && other.piece() == StandardPiece::Pawn
+                        && history.en_passant == Some(op_left.top(id.sign()).u())
+                    {
+                        let kind = MoveKind::EnPassant(op_left.u());
+                        return Some((Move::special(-1, 1, 1, kind), CanCapture::None));
                     }
-                    return None;
+                    None
                 })
             })
         }),
@@ -106,22 +135,25 @@ impl StandardPieceSet {
             board.get_pos(id).and_then(|op| {
                 let op_right = op.right(id.sign());
                 board.get_id(&op_right).and_then(|other| {
-                    if other.opposes(id) && other.piece() == StandardPiece::Pawn {
-                        return history.get_slice(&other).and_then(|prev| {
-                            prev.inner().last().and_then(|last| {
-                                if last == &op_right.bottom(other.sign()).bottom(other.sign()).u() {
-                                    return Some((Move::new(1, 1, 1), CanCapture::None));
-                                } else {
-                                    return None;
-                                }
-                            })
-                        });
+                    if other.opposes(id)
+                        && other.piece() == StandardPiece::Pawn
+                        && history.en_passant == Some(op_right.top(id.sign()).u())
+                    {
+                        let kind = MoveKind::EnPassant(op_right.u());
+                        return Some((Move::special(1, 1, 1, kind), CanCapture::None));
                     }
-                    return None;
+                    None
                 })
             })
         }),
     ];
+    const PAWN_PROMOTIONS: [StandardPiece; 4] = [
+        StandardPiece::Knight,
+        StandardPiece::Bishop,
+        StandardPiece::Rook,
+        StandardPiece::Queen,
+    ];
+    const PAWN_ATTACKS: [Move; 2] = [Move::new(-1, 1, 1), Move::new(1, 1, 1)];
     const KNIGHT_MOVESET: [CanMove<'static, StandardPiece>; 8] = [
         CanMove::Free(Move::new(1, 2, 1), CanCapture::Opposing(1)),
         CanMove::Free(Move::new(-1, 2, 1), CanCapture::Opposing(1)),
@@ -138,67 +170,11 @@ impl StandardPieceSet {
         CanMove::Free(Move::new(1, -1, usize::MAX), CanCapture::Opposing(1)),
         CanMove::Free(Move::new(-1, -1, usize::MAX), CanCapture::Opposing(1)),
     ];
-    const ROOK_MOVESET: [CanMove<'static, StandardPiece>; 6] = [
+    const ROOK_MOVESET: [CanMove<'static, StandardPiece>; 4] = [
         CanMove::Free(Move::new(1, 0, usize::MAX), CanCapture::Opposing(1)),
         CanMove::Free(Move::new(-1, 0, usize::MAX), CanCapture::Opposing(1)),
         CanMove::Free(Move::new(0, 1, usize::MAX), CanCapture::Opposing(1)),
         CanMove::Free(Move::new(0, -1, usize::MAX), CanCapture::Opposing(1)),
-        CanMove::Conditional(&|id, board, history| {
-            board.get_pos(id).and_then(|op| {
-                let idx1 = op.left(id.sign());
-                let idx2 = idx1.left(id.sign());
-                let idx3 = idx2.left(id.sign());
-                let idx4 = idx3.left(id.sign());
-                let other_match = |distance: isize| {
-                    move |other: PieceId<StandardPiece>| {
-                        if other.matches(id)
-                            && other.piece() == StandardPiece::King
-                            && history.get_slice(id).is_none()
-                            && history.get_slice(&other).is_none()
-                        {
-                            return Some((Move::new(distance, 0, 1), CanCapture::None));
-                        }
-                        return None;
-                    }
-                };
-                if board.get_id_not_none(&idx1).is_none() && board.get_id_not_none(&idx2).is_none()
-                {
-                    return board
-                        .get_id_not_none(&idx3)
-                        .and_then(other_match(-2))
-                        .or_else(|| board.get_id_not_none(&idx4).and_then(other_match(-3)));
-                }
-                return None;
-            })
-        }),
-        CanMove::Conditional(&|id, board, history| {
-            board.get_pos(id).and_then(|op| {
-                let idx1 = op.right(id.sign());
-                let idx2 = idx1.right(id.sign());
-                let idx3 = idx2.right(id.sign());
-                let idx4 = idx3.right(id.sign());
-                let other_match = |distance: isize| {
-                    move |other: PieceId<StandardPiece>| {
-                        if other.matches(id)
-                            && other.piece() == StandardPiece::King
-                            && history.get_slice(id).is_none()
-                            && history.get_slice(&other).is_none()
-                        {
-                            return Some((Move::new(distance, 0, 1), CanCapture::None));
-                        }
-                        return None;
-                    }
-                };
-                if board.get_id_not_none(&idx1).is_none() && board.get_id_not_none(&idx2).is_none()
-                {
-                    return board
-                        .get_id_not_none(&idx3)
-                        .and_then(other_match(2))
-                        .or_else(|| board.get_id_not_none(&idx4).and_then(other_match(3)));
-                }
-                return None;
-            })
-        }),
     ];
     const QUEEN_MOVESET: [CanMove<'static, StandardPiece>; 8] = [
         CanMove::Free(Move::new(1, 0, usize::MAX), CanCapture::Opposing(1)),
@@ -219,74 +195,85 @@ impl StandardPieceSet {
         CanMove::Free(Move::new(-1, 1, 1), CanCapture::Opposing(1)),
         CanMove::Free(Move::new(1, -1, 1), CanCapture::Opposing(1)),
         CanMove::Free(Move::new(-1, -1, 1), CanCapture::Opposing(1)),
-        CanMove::Conditional(&|id, board, history| {
-            board.get_pos(id).and_then(|op| {
-                let idx1 = op.left(id.sign());
-                let idx2 = idx1.left(id.sign());
-                let idx3 = idx2.left(id.sign());
-                let idx4 = idx3.left(id.sign());
-                let other_match = |other: PieceId<StandardPiece>| {
-                    if other.matches(id)
-                        && other.piece() == StandardPiece::Rook
-                        && history.get_slice(id).is_none()
-                        && history.get_slice(&other).is_none()
-                    {
-                        return Some((Move::new(-2, 0, 1), CanCapture::None));
-                    }
-                    return None;
-                };
-                if board.get_id_not_none(&idx1).is_none() && board.get_id_not_none(&idx2).is_none()
-                {
-                    return board
-                        .get_id_not_none(&idx3)
-                        .and_then(other_match)
-                        .or_else(|| board.get_id_not_none(&idx4).and_then(other_match));
-                }
-                return None;
-            })
-        }),
-        CanMove::Conditional(&|id, board, history| {
-            board.get_pos(id).and_then(|op| {
-                let idx1 = op.right(id.sign());
-                let idx2 = idx1.right(id.sign());
-                let idx3 = idx2.right(id.sign());
-                let idx4 = idx3.right(id.sign());
-                let other_match = |other: PieceId<StandardPiece>| {
-                    if other.matches(id)
-                        && other.piece() == StandardPiece::Rook
-                        && history.get_slice(id).is_none()
-                        && history.get_slice(&other).is_none()
-                    {
-                        return Some((Move::new(2, 0, 1), CanCapture::None));
-                    }
-                    return None;
-                };
-                if board.get_id_not_none(&idx1).is_none() && board.get_id_not_none(&idx2).is_none()
-                {
-                    return board
-                        .get_id_not_none(&idx3)
-                        .and_then(other_match)
-                        .or_else(|| board.get_id_not_none(&idx4).and_then(other_match));
-                }
-                return None;
-            })
-        }),
+        CanMove::Conditional(&|id, board, history| Self::castle(id, board, history, -1)),
+        CanMove::Conditional(&|id, board, history| Self::castle(id, board, history, 1)),
     ];
 
+    /// The row of `pos` counted from the back rank of the side with `sign`.
+    fn rank(sign: Sign, pos: &PiecePos<StandardPiece>) -> isize {
+        let (_, y): (isize, isize) = pos.into();
+        match sign {
+            Sign::Negative => pos.1.get_col_size() as isize - 1 - y,
+            _ => y,
+        }
+    }
+
+    /// Castles towards `direction` (relative to the king's sign) with the nearest
+    /// unmoved rook that way, if the king has not moved either. Following Chess960,
+    /// wherever the two start, the king lands two files in from the edge it castles
+    /// towards, on g or c, and the rook next to it towards the centre. Every square
+    /// either of them passes over or lands on must be empty, apart from their own.
+    fn castle(
+        id: &PieceId<StandardPiece>,
+        board: &dyn Board<PieceType = StandardPiece>,
+        history: &BoardHistory,
+        direction: isize,
+    ) -> Option<(Move, CanCapture<'static, StandardPiece>)> {
+        if history.get_slice(id).is_some() {
+            return None;
+        }
+        let (x, y): (isize, isize) = board.get_pos(id)?.into();
+        let step = id.sign() * direction;
+        let mut rx = x + step;
+        let rook_pos = loop {
+            if !PiecePos::is_inbounds(rx, y, board) {
+                return None;
+            }
+            let pos = PiecePos::from((rx, y, board));
+            if board.get_id_not_none(&pos).is_some_and(|other| {
+                other.matches(id)
+                    && other.piece() == StandardPiece::Rook
+                    && history.get_slice(&other).is_none()
+            }) {
+                break pos;
+            }
+            rx += step;
+        };
+        let row_size = board.get_row_size() as isize;
+        let (king_to, rook_to) = match step {
+            1 => (row_size - 2, row_size - 3),
+            _ => (2, 3),
+        };
+        let low = x.min(rx).min(king_to).min(rook_to);
+        let high = x.max(rx).max(king_to).max(rook_to);
+        let blocked = (low..=high).filter(|bx| *bx != x && *bx != rx).any(|bx| {
+            board
+                .get_id_not_none(&PiecePos::from((bx, y, board)))
+                .is_some()
+        });
+        if blocked {
+            return None;
+        }
+        let rook_to = PiecePos::from((rook_to, y, board)).u();
+        let kind = MoveKind::Castle(rook_pos.u(), rook_to);
+        let king_move = Move::special(id.sign() * (king_to - x), 0, 1, kind);
+        Some((king_move, CanCapture::Specific(&|_, _, _| true)))
+    }
+
     fn valid_moves(
         &self,
         piece_id: &PieceId<StandardPiece>,
         board: &dyn Board<PieceType = StandardPiece>,
         history: &BoardHistory,
-    ) -> Option<Vec<usize>> {
+    ) -> Option<Vec<Target>> {
         let mut valid = Vec::new();
         let pos = board.get_pos(piece_id)?;
         let moveset = self.moveset(&piece_id.piece())?;
         for can_move in moveset {
             let mut move_op = match can_move {
-                CanMove::Free(m, c) => m.calculate(piece_id, &pos, c, board),
+                CanMove::Free(m, c) => m.calculate_targets(piece_id, &pos, c, board),
                 CanMove::Conditional(c) => match c(piece_id, board, history) {
-                    Some((m, c)) => m.calculate(piece_id, &pos, &c, board),
+                    Some((m, c)) => m.calculate_targets(piece_id, &pos, &c, board),
                     None => Vec::new(),
                 },
             };
@@ -309,15 +296,106 @@ impl PieceSet<'static> for StandardPieceSet {
             StandardPiece::Queen => &Self::QUEEN_MOVESET[..],
             StandardPiece::King => &Self::KING_MOVESET[..],
         };
-        return Some(moveset);
+        Some(moveset)
     }
 
-    fn valid_slice(
+    fn valid_targets(
         &self,
         piece_id: &PieceId<StandardPiece>,
         board: &dyn Board<PieceType = StandardPiece>,
         history: &BoardHistory,
+    ) -> Vec<Target> {
+        let mut targets = self
+            .valid_moves(piece_id, board, history)
+            .unwrap_or_default();
+        targets.retain(|target| self.leaves_royal_safe(piece_id, target, board));
+        targets
+    }
+
+    fn royal(&self) -> StandardPiece {
+        StandardPiece::King
+    }
+
+    fn castles_with(&self, piece: &StandardPiece) -> bool {
+        piece == &StandardPiece::Rook
+    }
+
+    fn value(&self, piece: &StandardPiece) -> isize {
+        match piece {
+            StandardPiece::None | StandardPiece::King => 0,
+            StandardPiece::Pawn => 1,
+            StandardPiece::Knight | StandardPiece::Bishop => 3,
+            StandardPiece::Rook => 5,
+            StandardPiece::Queen => 9,
+        }
+    }
+
+    fn is_irreversible(&self, piece: &StandardPiece) -> bool {
+        piece == &StandardPiece::Pawn
+    }
+
+    /// Kings alone, a single knight or bishop besides them, or only bishops that all
+    /// stand on squares of the same colour.
+    fn insufficient_material(&self, board: &dyn Board<PieceType = StandardPiece>) -> bool {
+        let mut knights = 0;
+        let mut bishops = [false; 2];
+        for u in 0..board.get_board_size() {
+            let id = match board.get_id_not_none(&PiecePos(u, board)) {
+                Some(id) => id,
+                None => continue,
+            };
+            match id.piece() {
+                StandardPiece::King => (),
+                StandardPiece::Knight => knights += 1,
+                StandardPiece::Bishop => {
+                    let square = Square::from_index(u, board.get_row_size());
+                    bishops[(square.x + square.y) % 2] = true;
+                }
+                _ => return false,
+            }
+        }
+        match knights {
+            0 => !(bishops[0] && bishops[1]),
+            1 => !(bishops[0] || bishops[1]),
+            _ => false,
+        }
+    }
+
+    fn promotions(
+        &self,
+        piece_id: &PieceId<StandardPiece>,
+        square: usize,
+        board: &dyn Board<PieceType = StandardPiece>,
+    ) -> &[StandardPiece] {
+        if piece_id.piece() != StandardPiece::Pawn {
+            return &[];
+        }
+        let last_row = board.get_col_size() as isize - 1;
+        if Self::rank(piece_id.sign(), &PiecePos(square, board)) == last_row {
+            return &Self::PAWN_PROMOTIONS;
+        }
+        &[]
+    }
+
+    fn attacks(
+        &self,
+        piece_id: &PieceId<StandardPiece>,
+        board: &dyn Board<PieceType = StandardPiece>,
     ) -> BoardSlice {
-        BoardSlice::new(self.valid_moves(piece_id, board, history))
+        let mut attacked = Vec::new();
+        if let Some(pos) = board.get_pos(piece_id) {
+            if piece_id.piece() == StandardPiece::Pawn {
+                for m in &Self::PAWN_ATTACKS {
+                    attacked.append(&mut m.calculate(piece_id, &pos, &CanCapture::All, board));
+                }
+            } else if let Some(moveset) = self.moveset(&piece_id.piece()) {
+                for can_move in moveset {
+                    if let CanMove::Free(m, c) = can_move {
+                        attacked.append(&mut m.calculate(piece_id, &pos, c, board));
+                    }
+                }
+            }
+        }
+        BoardSlice::new(Some(attacked))
     }
 }
@@ -0,0 +1,167 @@
+use std::mem::size_of;
+
+use crate::chess::search::SearchMove;
+
+const MEGABYTE: usize = 1024 * 1024;
+
+/// How a stored score relates to the true score of the position.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Bound {
+    /// The score is exact.
+    Exact,
+    /// The search failed high: the true score is at least this much.
+    Lower,
+    /// The search failed low: the true score is at most this much.
+    Upper,
+}
+
+/// What a search found out about a position.
+#[derive(Debug, Clone, Copy)]
+pub struct Entry<P> {
+    pub key: u64,
+    pub depth: usize,
+    pub bound: Bound,
+    pub score: isize,
+    pub best_move: Option<SearchMove<P>>,
+    generation: u8,
+}
+
+/// A fixed-size table of search results indexed by the Zobrist key of the position,
+/// so that positions reached again by another order of moves are not searched
+/// again. When two positions compete for a slot, results of an earlier search give
+/// way to anything, and otherwise the deeper result stays.
+pub struct TranspositionTable<P> {
+    entries: Vec<Option<Entry<P>>>,
+    generation: u8,
+}
+
+impl<P: Copy> TranspositionTable<P> {
+    /// A table taking up about `megabytes` of memory, with room for at least one
+    /// entry.
+    pub fn new(megabytes: usize) -> Self {
+        let mut table = TranspositionTable {
+            entries: Vec::new(),
+            generation: 0,
+        };
+        table.resize(megabytes);
+        table
+    }
+
+    /// Changes the size of the table, which forgets everything in it.
+    pub fn resize(&mut self, megabytes: usize) {
+        let len = (megabytes * MEGABYTE / size_of::<Option<Entry<P>>>()).max(1);
+        self.entries = vec![None; len];
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+        self.generation = 0;
+    }
+
+    /// Marks the results stored so far as coming from an earlier search.
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    pub fn probe(&self, key: u64) -> Option<&Entry<P>> {
+        self.entries[self.index(key)]
+            .as_ref()
+            .filter(|entry| entry.key == key)
+    }
+
+    pub fn store(
+        &mut self,
+        key: u64,
+        depth: usize,
+        bound: Bound,
+        score: isize,
+        best_move: Option<SearchMove<P>>,
+    ) {
+        let generation = self.generation;
+        let index = self.index(key);
+        let slot = &mut self.entries[index];
+        let replace = match slot {
+            Some(entry) => {
+                entry.key == key || entry.generation != generation || depth >= entry.depth
+            }
+            None => true,
+        };
+        if !replace {
+            return;
+        }
+        // Keep the move of an earlier result for the same position when this one
+        // has none, since it is still the best guess for move ordering.
+        let best_move = match slot {
+            Some(entry) if entry.key == key && best_move.is_none() => entry.best_move,
+            _ => best_move,
+        };
+        *slot = Some(Entry {
+            key,
+            depth,
+            bound,
+            score,
+            best_move,
+            generation,
+        });
+    }
+
+    fn index(&self, key: u64) -> usize {
+        key as usize % self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bound, TranspositionTable};
+
+    /// A table with a single slot, which every key competes for.
+    fn single_slot() -> TranspositionTable<u8> {
+        TranspositionTable::new(0)
+    }
+
+    fn depth_of(table: &TranspositionTable<u8>, key: u64) -> Option<usize> {
+        table.probe(key).map(|entry| entry.depth)
+    }
+
+    #[test]
+    fn same_position_is_always_replaced() {
+        let mut table = single_slot();
+        table.store(1, 8, Bound::Exact, 50, None);
+        table.store(1, 2, Bound::Lower, 70, None);
+        let entry = table.probe(1).unwrap();
+        assert_eq!(
+            (entry.depth, entry.bound, entry.score),
+            (2, Bound::Lower, 70)
+        );
+    }
+
+    #[test]
+    fn earlier_searches_give_way() {
+        let mut table = single_slot();
+        table.store(1, 8, Bound::Exact, 50, None);
+        table.new_search();
+        table.store(2, 1, Bound::Exact, 30, None);
+        assert_eq!(depth_of(&table, 1), None);
+        assert_eq!(depth_of(&table, 2), Some(1));
+    }
+
+    #[test]
+    fn deeper_results_stay() {
+        let mut table = single_slot();
+        table.store(1, 8, Bound::Exact, 50, None);
+        table.store(2, 3, Bound::Exact, 30, None);
+        assert_eq!(depth_of(&table, 1), Some(8));
+        assert_eq!(depth_of(&table, 2), None);
+        table.store(2, 8, Bound::Exact, 30, None);
+        assert_eq!(depth_of(&table, 1), None);
+        assert_eq!(depth_of(&table, 2), Some(8));
+    }
+
+    #[test]
+    fn resizing_forgets_everything() {
+        let mut table = TranspositionTable::<u8>::new(1);
+        table.store(1, 8, Bound::Exact, 50, None);
+        table.resize(2);
+        assert_eq!(depth_of(&table, 1), None);
+    }
+}
@@ -0,0 +1,44 @@
+// Keys for Zobrist hashing. Rather than drawing them from a random number generator
+// at startup, every key is derived from what it stands for, so that a position has
+// the same key in every run and on every machine.
+
+const PIECE: u64 = 1;
+const CASTLING: u64 = 2;
+const EN_PASSANT: u64 = 3;
+const SIDE: u64 = 4;
+
+/// SplitMix64, which spreads consecutive inputs over the whole range of outputs.
+fn mix(n: u64) -> u64 {
+    let mut z = n.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn key(kind: u64, a: u64, b: u64) -> u64 {
+    mix(kind << 56 ^ a << 24 ^ b)
+}
+
+/// The key of the piece stored as `i` on the board standing on `square`. Empty
+/// squares have no key.
+pub fn piece(i: isize, square: usize) -> u64 {
+    if i == 0 {
+        return 0;
+    }
+    key(PIECE, i as u8 as u64, square as u64)
+}
+
+/// The key of the right to castle with the piece on `square`.
+pub fn castling(square: usize) -> u64 {
+    key(CASTLING, 0, square as u64)
+}
+
+/// The key of an en passant capture being possible on `file`.
+pub fn en_passant(file: usize) -> u64 {
+    key(EN_PASSANT, 0, file as u64)
+}
+
+/// The key of the second side, black in chess, being to move.
+pub fn side() -> u64 {
+    key(SIDE, 0, 0)
+}
@@ -0,0 +1,114 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::chess::{
+    game::{DrawRule, GameResult},
+    movement::MoveKind,
+    piece::Sign,
+    search::{Search, SearchLimits, SearchMove, SearchResult},
+    square::{AlgebraicMove, Square},
+    standard::{eval::StandardEvaluator, piece::StandardPiece, BitBoardGame},
+    transposition::TranspositionTable,
+};
+
+/// The size of the transposition table in megabytes, unless configured otherwise.
+pub const DEFAULT_HASH: usize = 16;
+
+/// The largest transposition table a front-end will allocate, in megabytes.
+pub const MAX_HASH: usize = 4096;
+
+/// A transposition table kept between searches and handed to each one in turn.
+pub type SharedTable = Arc<Mutex<TranspositionTable<StandardPiece>>>;
+
+pub fn shared_table(megabytes: usize) -> SharedTable {
+    Arc::new(Mutex::new(TranspositionTable::new(megabytes)))
+}
+
+/// A search running on its own thread, so that a protocol front-end can keep
+/// reading commands while the engine thinks.
+pub struct Thinking {
+    handle: JoinHandle<()>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Thinking {
+    /// Searches `game` within `limits` using `table`, handing every completed iteration to `info`
+    /// and the final result to `report`. With `until_stopped` the result is held back
+    /// until `stop` is called, even when the search ends sooner.
+    pub fn start<I, F>(
+        mut game: BitBoardGame,
+        limits: SearchLimits,
+        table: SharedTable,
+        until_stopped: bool,
+        info: I,
+        report: F,
+    ) -> Self
+    where
+        I: FnMut(&SearchResult<StandardPiece>) + Send + 'static,
+        F: FnOnce(SearchResult<StandardPiece>) + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&stop);
+        let handle = thread::spawn(move || {
+            let mut table = table.lock().unwrap();
+            let result =
+                Search::new(limits, StandardEvaluator, &mut table, &flag).run(&mut game, info);
+            drop(table);
+            while until_stopped && !flag.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(10));
+            }
+            report(result);
+        });
+        Thinking { handle, stop }
+    }
+
+    /// Makes the search report what it has found so far and waits until it has.
+    pub fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.handle.join();
+    }
+}
+
+/// The move a search found, in long algebraic notation. With `chess960`, castling
+/// is written as the king moving onto the rook, as Chess960 interfaces expect.
+pub fn algebraic(search_move: &SearchMove<StandardPiece>, chess960: bool) -> AlgebraicMove {
+    let to = match search_move.target.kind() {
+        MoveKind::Castle(rook_from, _) if chess960 => rook_from,
+        _ => search_move.target.u(),
+    };
+    AlgebraicMove::new(
+        Square::from_index(search_move.from, 8),
+        Square::from_index(to, 8),
+        search_move.promotion.map(|piece| piece.symbol()),
+    )
+}
+
+/// A line of play in long algebraic notation, separated by spaces.
+pub fn line(moves: &[SearchMove<StandardPiece>], chess960: bool) -> String {
+    moves
+        .iter()
+        .map(|search_move| algebraic(search_move, chess960).to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// How a game ended, in the words of the interfaces we talk to.
+pub fn describe(result: GameResult) -> &'static str {
+    match result {
+        GameResult::Ongoing => "Game in progress",
+        GameResult::Checkmate(Sign::Positive) => "White mates",
+        GameResult::Checkmate(_) => "Black mates",
+        GameResult::Stalemate => "Stalemate",
+        GameResult::Draw(DrawRule::FiftyMoves) => "Draw by fifty move rule",
+        GameResult::Draw(DrawRule::ThreefoldRepetition) => "Draw by repetition",
+        GameResult::Draw(DrawRule::InsufficientMaterial) => "Insufficient material",
+        GameResult::Resignation(Sign::Positive) => "Black resigns",
+        GameResult::Resignation(_) => "White resigns",
+    }
+}
@@ -1,28 +1,76 @@
-use crate::chess::{
-    game::Game,
-    piece::{PieceId, Sign},
-    standard::{
-        board::StandardBoard,
-        piece::{StandardPiece, StandardPieceSet},
+use std::{
+    io::{self, BufRead, IsTerminal},
+    time::Instant,
+};
+
+use crate::{
+    chess::{
+        piece::{PieceId, Sign},
+        standard::{fen::STARTING_FEN, piece::StandardPiece, BitBoardGame, StandardGame},
     },
+    repl::Repl,
+    uci::Uci,
+    xboard::XBoard,
 };
 
 mod chess;
+mod engine;
+mod repl;
+mod uci;
+mod xboard;
 
+/// Waits for the first command to pick the protocol to speak, falling back to the
+/// interactive session for anything else. On a terminal the session starts right
+/// away. Running with `demo` plays through a few moves on the console instead, and
+/// `perft <depth> [fen]` counts the positions below every move.
 fn main() {
-    let mut game = Game::<StandardBoard<8, 8, 64, StandardPiece>, StandardPieceSet>::new(
-        [
-            4, 2, 3, 5, 6, 3, 2, 4, //
-            1, 1, 1, 1, 1, 1, 1, 1, //
-            0, 0, 0, 0, 0, 0, 0, 0, //
-            0, 0, 0, 0, 0, 0, 0, 0, //
-            0, 0, 0, 0, 0, 0, 0, 0, //
-            0, 0, 0, 0, 0, 0, 0, 0, //
-            -1, -1, -1, -1, -1, -1, -1, -1, //
-            -4, -2, -3, -5, -6, -3, -2, -4, //
-        ],
-        StandardPieceSet,
-    );
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    match arguments.first().map(String::as_str) {
+        Some("demo") => return demo(),
+        Some("perft") => return perft(&arguments[1..]),
+        _ => (),
+    }
+    let mut input = io::stdin().lock();
+    if io::stdin().is_terminal() {
+        return Repl::new(true).run(None, &mut input);
+    }
+    let mut line = String::new();
+    while input.read_line(&mut line).is_ok_and(|read| read > 0) {
+        match line.trim() {
+            "" => line.clear(),
+            "uci" => return Uci::new().run(&mut input),
+            "xboard" => return XBoard::new().run(&mut input),
+            command => return Repl::new(false).run(Some(command), &mut input),
+        }
+    }
+}
+
+fn perft(arguments: &[String]) {
+    let depth = match arguments.first().and_then(|depth| depth.parse().ok()) {
+        Some(depth) => depth,
+        None => return eprintln!("usage: perft <depth> [fen]"),
+    };
+    let fen = match arguments.len() {
+        1 => STARTING_FEN.to_string(),
+        _ => arguments[1..].join(" "),
+    };
+    let mut game = match BitBoardGame::from_fen(&fen) {
+        Ok(game) => game,
+        Err(error) => return eprintln!("invalid position: {}", error),
+    };
+    let started = Instant::now();
+    let mut total = 0;
+    for (algebraic, nodes) in game.divide(depth) {
+        println!("{}: {}", algebraic, nodes);
+        total += nodes;
+    }
+    println!();
+    println!("Nodes searched: {}", total);
+    println!("Time: {} ms", started.elapsed().as_millis());
+}
+
+fn demo() {
+    let mut game = StandardGame::from_fen(STARTING_FEN).unwrap();
     let my_pawn = &PieceId(StandardPiece::Pawn, Sign::Positive, 3);
     let my_rook = &PieceId(StandardPiece::Rook, Sign::Positive, 0);
     let my_knight = &PieceId(StandardPiece::Knight, Sign::Positive, 0);
@@ -30,33 +78,33 @@ fn main() {
     let my_queen = &PieceId(StandardPiece::Queen, Sign::Positive, 0);
     let my_king = &PieceId(StandardPiece::King, Sign::Positive, 0);
 
+    game.set_analysis(true);
     game.visualize_board();
     game.visualize_moves(my_pawn);
-    game.move_piece_relative(my_pawn, 16);
+    game.move_piece_relative(my_pawn, 16).unwrap();
     game.visualize_board();
     game.visualize_moves(my_pawn);
-    game.move_piece_relative(my_pawn, 8);
+    game.move_piece_relative(my_pawn, 8).unwrap();
     game.visualize_board();
     game.visualize_moves(my_pawn);
-    game.move_piece_relative(my_pawn, 8);
+    game.move_piece_relative(my_pawn, 8).unwrap();
     game.visualize_board();
     game.visualize_moves(my_pawn);
-    game.move_piece_relative(my_pawn, 7);
+    game.move_piece_relative(my_pawn, 7).unwrap();
     game.visualize_board();
     game.visualize_moves(my_knight);
-    game.move_piece_relative(my_knight, 15);
+    game.move_piece_relative(my_knight, 15).unwrap();
     game.visualize_board();
     game.visualize_moves(my_bishop);
-    game.move_piece_relative(my_bishop, 27);
+    game.move_piece_relative(my_bishop, 27).unwrap();
     game.visualize_board();
     game.visualize_moves(my_queen);
-    game.move_piece_relative(my_queen, 8);
+    game.move_piece_relative(my_queen, 8).unwrap();
     game.visualize_board();
     game.visualize_moves(my_king);
-    game.move_piece(my_king, 2);
-    game.move_piece(my_rook, 3);
+    game.move_piece(my_king, 2).unwrap();
     game.visualize_board();
     game.visualize_moves(my_rook);
-    game.move_piece_relative(my_rook, 1);
+    game.move_piece_relative(my_rook, 1).unwrap();
     game.visualize_board();
 }
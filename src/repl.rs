@@ -0,0 +1,222 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{
+    chess::{
+        game::GameResult,
+        piece::Sign,
+        square::{AlgebraicMove, Square},
+        standard::{
+            chess960,
+            eval::StandardEvaluator,
+            fen::STARTING_FEN,
+            pgn::{result_tag, Pgn},
+            BitBoardGame,
+        },
+    },
+    engine::describe,
+};
+
+const HELP: &str = "\
+e2e4, Nf3       play a move in long or standard algebraic notation
+show            print the board
+moves [square]  list the legal moves, or show those of the piece on a square
+undo, redo      take back a move or play it again
+fen             print the position in FEN
+pgn [file]      print the game in PGN, or write it to a file
+load <fen>      set up a position from a FEN
+load <file> [n] replay game n of a PGN file, the first one by default
+chess960 <n>    set up the Chess960 start position numbered n, from 0 to 959
+flip            turn the board around
+eval            print the static evaluation
+captured        list the pieces taken from each side
+quit            leave";
+
+/// An interactive session for playing through and inspecting a game from the
+/// terminal.
+pub struct Repl {
+    game: BitBoardGame,
+    prompt: bool,
+}
+
+impl Repl {
+    /// With `prompt`, a prompt is printed whenever the session waits for a command.
+    pub fn new(prompt: bool) -> Self {
+        Repl {
+            game: BitBoardGame::from_fen(STARTING_FEN).unwrap(),
+            prompt,
+        }
+    }
+
+    /// Serves commands until `quit` or the end of the input, starting with `first`
+    /// if a command was already read.
+    pub fn run(&mut self, first: Option<&str>, input: &mut impl BufRead) {
+        if first.is_some_and(|line| !self.command(line)) {
+            return;
+        }
+        let mut line = String::new();
+        loop {
+            if self.prompt {
+                print!("> ");
+                let _ = io::stdout().flush();
+            }
+            line.clear();
+            if !input.read_line(&mut line).is_ok_and(|read| read > 0) {
+                break;
+            }
+            if !self.command(&line) {
+                break;
+            }
+        }
+    }
+
+    /// Handles a single command. Returns whether the session should go on.
+    pub fn command(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let (command, arguments) = match tokens.split_first() {
+            Some((command, arguments)) => (*command, arguments),
+            None => return true,
+        };
+        match command {
+            "help" => println!("{}", HELP),
+            "show" => self.game.visualize_board(),
+            "moves" => self.moves(arguments.first()),
+            "undo" => {
+                if !self.game.undo() {
+                    println!("there is no move to take back");
+                }
+            }
+            "redo" => {
+                if !self.game.redo() {
+                    println!("there is no move to play again");
+                }
+            }
+            "fen" => println!("{}", self.game.to_fen()),
+            "pgn" => self.pgn(arguments.first()),
+            "load" => self.load(&arguments.join(" ")),
+            "chess960" => {
+                let fen = arguments
+                    .first()
+                    .and_then(|index| index.parse().ok())
+                    .and_then(chess960::start_fen);
+                match fen {
+                    Some(fen) => self.load(&fen),
+                    None => println!("expected a number from 0 to {}", chess960::POSITIONS - 1),
+                }
+            }
+            "flip" => {
+                self.game.set_flipped(!self.game.is_flipped());
+                self.game.visualize_board();
+            }
+            "eval" => println!(
+                "{:+} centipawns for the side to move, material {:+}",
+                self.game.evaluate(&StandardEvaluator),
+                self.game.material_balance()
+            ),
+            "captured" => {
+                for (name, sign) in [("white", Sign::Positive), ("black", Sign::Negative)] {
+                    let symbols: Vec<String> = self
+                        .game
+                        .captured(sign)
+                        .iter()
+                        .map(|id| id.piece().symbol().to_string())
+                        .collect();
+                    println!("{}: {}", name, symbols.join(" "));
+                }
+            }
+            "quit" | "exit" => return false,
+            _ => self.play(command),
+        }
+        true
+    }
+
+    fn moves(&mut self, square: Option<&&str>) {
+        let name = match square {
+            Some(name) => name,
+            None => {
+                let mut moves = Vec::new();
+                for (id, target) in self.game.legal_moves(self.game.side_to_move()) {
+                    let promotions = self.game.promotions(&id, target.u());
+                    if promotions.is_empty() {
+                        moves.push(self.game.san(&id, &target, None));
+                    }
+                    for promotion in promotions {
+                        moves.push(self.game.san(&id, &target, Some(promotion)));
+                    }
+                }
+                println!("{}", moves.join(" "));
+                return;
+            }
+        };
+        let piece = name
+            .parse::<Square>()
+            .ok()
+            .and_then(|square| self.game.piece_at(square));
+        match piece {
+            Some(id) => self.game.visualize_moves(&id),
+            None => println!("there is no piece on {}", name),
+        }
+    }
+
+    fn pgn(&mut self, file: Option<&&str>) {
+        let pgn = self.game.export_pgn().to_string();
+        match file {
+            Some(file) => {
+                if let Err(error) = std::fs::write(file, pgn) {
+                    println!("cannot write {}: {}", file, error);
+                }
+            }
+            None => print!("{}", pgn),
+        }
+    }
+
+    fn load(&mut self, source: &str) {
+        let loaded = match BitBoardGame::from_fen(source) {
+            Ok(game) => Ok(game),
+            Err(error) => {
+                let mut arguments = source.split_whitespace();
+                let file = arguments.next().unwrap_or_default();
+                let number = arguments.next().map_or(Some(1), |n| n.parse().ok());
+                match (std::fs::read_to_string(file), number) {
+                    (Ok(text), Some(number)) => Self::pgn_game(&text, number),
+                    (Ok(_), None) => Err("expected the number of a game".to_string()),
+                    (Err(_), _) => Err(error.to_string()),
+                }
+            }
+        };
+        match loaded {
+            Ok(game) => {
+                self.game = game;
+                self.game.visualize_board();
+            }
+            Err(error) => println!("cannot load {}: {}", source, error),
+        }
+    }
+
+    /// Replays game `number` of the PGN `text`, counting from one.
+    fn pgn_game(text: &str, number: usize) -> Result<BitBoardGame, String> {
+        let games = Pgn::parse_all(text).map_err(|error| error.to_string())?;
+        match number.checked_sub(1).and_then(|i| games.get(i)) {
+            Some(pgn) => BitBoardGame::from_pgn(pgn).map_err(|error| error.to_string()),
+            None => Err(format!("there is no game {} among {}", number, games.len())),
+        }
+    }
+
+    fn play(&mut self, text: &str) {
+        let played = match text.parse::<AlgebraicMove>() {
+            Ok(algebraic) => self
+                .game
+                .play_move(&algebraic)
+                .map_err(|error| error.to_string()),
+            Err(_) => self.game.play_san(text).map_err(|error| error.to_string()),
+        };
+        if let Err(error) = played {
+            println!("{}", error);
+            return;
+        }
+        self.game.visualize_board();
+        let status = self.game.status();
+        if status != GameResult::Ongoing {
+            println!("{} {}", result_tag(status), describe(status));
+        }
+    }
+}
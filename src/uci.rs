@@ -0,0 +1,264 @@
+use std::{io::BufRead, sync::Arc, time::Duration};
+
+use crate::{
+    chess::{
+        piece::Sign,
+        search::{time_budget, SearchLimits},
+        square::AlgebraicMove,
+        standard::{fen::STARTING_FEN, magic, BitBoardGame},
+    },
+    engine::{algebraic, line, shared_table, SharedTable, Thinking, DEFAULT_HASH, MAX_HASH},
+};
+
+const DEFAULT_MOVE_OVERHEAD: u64 = 30;
+
+/// An engine speaking the Universal Chess Interface over standard input and output.
+/// Searches run on their own thread so that `stop` and `isready` are answered while
+/// the engine thinks.
+pub struct Uci {
+    game: BitBoardGame,
+    move_overhead: Duration,
+    /// Whether castling is written as the king moving onto the rook, as Chess960
+    /// interfaces expect.
+    chess960: bool,
+    table: SharedTable,
+    thinking: Option<Thinking>,
+}
+
+impl Uci {
+    pub fn new() -> Self {
+        Uci {
+            game: BitBoardGame::from_fen(STARTING_FEN).unwrap(),
+            move_overhead: Duration::from_millis(DEFAULT_MOVE_OVERHEAD),
+            chess960: false,
+            table: shared_table(DEFAULT_HASH),
+            thinking: None,
+        }
+    }
+
+    /// Answers the `uci` command that selected the protocol, then serves commands
+    /// until `quit` or the end of the input. The attack tables are filled first, so
+    /// that the first search does not spend its time on them.
+    pub fn run(&mut self, input: &mut impl BufRead) {
+        magic::init();
+        self.command("uci");
+        for line in input.lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            if !self.command(&line) {
+                break;
+            }
+        }
+        self.stop();
+    }
+
+    /// Handles a single command. Returns whether the engine should keep running.
+    pub fn command(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let (command, arguments) = match tokens.split_first() {
+            Some((command, arguments)) => (*command, arguments),
+            None => return true,
+        };
+        match command {
+            "uci" => {
+                println!(
+                    "id name {} {}",
+                    env!("CARGO_PKG_NAME"),
+                    env!("CARGO_PKG_VERSION")
+                );
+                println!(
+                    "option name Hash type spin default {} min 1 max {}",
+                    DEFAULT_HASH, MAX_HASH
+                );
+                println!(
+                    "option name Move Overhead type spin default {} min 0 max 5000",
+                    DEFAULT_MOVE_OVERHEAD
+                );
+                println!("option name UCI_Chess960 type check default false");
+                println!("uciok");
+            }
+            "isready" => println!("readyok"),
+            "ucinewgame" => {
+                self.stop();
+                self.game = BitBoardGame::from_fen(STARTING_FEN).unwrap();
+                self.table.lock().unwrap().clear();
+            }
+            "position" => {
+                self.stop();
+                self.position(arguments);
+            }
+            "go" => {
+                self.stop();
+                self.go(arguments);
+            }
+            "stop" => self.stop(),
+            "setoption" => {
+                self.stop();
+                self.set_option(arguments);
+            }
+            "quit" => return false,
+            _ => println!("info string unknown command {}", command),
+        }
+        true
+    }
+
+    fn position(&mut self, arguments: &[&str]) {
+        let moves = arguments.iter().position(|token| *token == "moves");
+        let (setup, moves) = match moves {
+            Some(i) => (&arguments[..i], &arguments[i + 1..]),
+            None => (arguments, &arguments[arguments.len()..]),
+        };
+        let fen = match setup.split_first() {
+            Some((&"startpos", _)) => STARTING_FEN.to_string(),
+            Some((&"fen", fields)) => fields.join(" "),
+            _ => {
+                println!("info string expected startpos or fen");
+                return;
+            }
+        };
+        self.game = match BitBoardGame::from_fen(&fen) {
+            Ok(game) => game,
+            Err(error) => {
+                println!("info string {}", error);
+                return;
+            }
+        };
+        for text in moves {
+            let played = text
+                .parse::<AlgebraicMove>()
+                .map_err(|error| error.to_string())
+                .and_then(|algebraic| {
+                    self.game
+                        .play_move(&algebraic)
+                        .map_err(|error| error.to_string())
+                });
+            if let Err(error) = played {
+                println!("info string cannot play {}: {}", text, error);
+                return;
+            }
+        }
+    }
+
+    fn go(&mut self, arguments: &[&str]) {
+        let mut limits = SearchLimits::default();
+        let mut clock = [None, None];
+        let mut increment = [Duration::ZERO, Duration::ZERO];
+        let mut moves_to_go = None;
+        let mut infinite = false;
+        let mut tokens = arguments.iter();
+        while let Some(token) = tokens.next() {
+            let mut value = || tokens.next().and_then(|value| value.parse::<u64>().ok());
+            match *token {
+                "depth" => limits.depth = value().map(|depth| depth as usize),
+                "movetime" => {
+                    limits.time = value()
+                        .map(|time| Duration::from_millis(time).saturating_sub(self.move_overhead))
+                }
+                "wtime" => clock[0] = value().map(Duration::from_millis),
+                "btime" => clock[1] = value().map(Duration::from_millis),
+                "winc" => increment[0] = value().map_or(Duration::ZERO, Duration::from_millis),
+                "binc" => increment[1] = value().map_or(Duration::ZERO, Duration::from_millis),
+                "movestogo" => moves_to_go = value().map(|moves| moves as usize),
+                "infinite" => infinite = true,
+                _ => (),
+            }
+        }
+        let side = match self.game.side_to_move() {
+            Sign::Negative => 1,
+            _ => 0,
+        };
+        if let (None, Some(remaining)) = (limits.time, clock[side]) {
+            limits.time = Some(time_budget(
+                remaining,
+                increment[side],
+                moves_to_go,
+                self.move_overhead,
+            ));
+        }
+        if infinite {
+            limits = SearchLimits::default();
+        }
+        let chess960 = self.chess960;
+        let thinking = Thinking::start(
+            self.game.clone(),
+            limits,
+            Arc::clone(&self.table),
+            infinite,
+            move |result| {
+                let score = match result.mate_in() {
+                    Some(moves) => format!("mate {}", moves),
+                    None => format!("cp {}", result.score),
+                };
+                println!(
+                    "info depth {} score {} nodes {} nps {} time {} pv {}",
+                    result.depth,
+                    score,
+                    result.nodes,
+                    result.nodes_per_second(),
+                    result.time.as_millis(),
+                    line(&result.pv, chess960)
+                );
+            },
+            move |result| match result.best_move {
+                Some(best_move) => println!("bestmove {}", algebraic(&best_move, chess960)),
+                None => println!("bestmove 0000"),
+            },
+        );
+        self.thinking = Some(thinking);
+    }
+
+    /// Stops the running search, if any, and waits for it to report its move.
+    fn stop(&mut self) {
+        if let Some(thinking) = self.thinking.take() {
+            thinking.stop();
+        }
+    }
+
+    fn set_option(&mut self, arguments: &[&str]) {
+        let value = arguments.iter().position(|token| *token == "value");
+        let name = arguments[..value.unwrap_or(arguments.len())]
+            .iter()
+            .skip_while(|token| **token == "name")
+            .copied()
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let value = value.map(|i| arguments[i + 1..].join(" "));
+        match (name.to_lowercase().as_str(), value) {
+            ("hash", Some(value)) => match value.parse() {
+                Ok(megabytes) if (1..=MAX_HASH).contains(&megabytes) => {
+                    self.table.lock().unwrap().resize(megabytes)
+                }
+                _ => println!("info string invalid Hash {}", value),
+            },
+            ("move overhead", Some(value)) => match value.parse() {
+                Ok(overhead) => self.move_overhead = Duration::from_millis(overhead),
+                Err(_) => println!("info string invalid Move Overhead {}", value),
+            },
+            ("uci_chess960", Some(value)) => match value.parse() {
+                Ok(chess960) => self.chess960 = chess960,
+                Err(_) => println!("info string invalid UCI_Chess960 {}", value),
+            },
+            _ => println!("info string unknown option {}", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Uci;
+    use crate::chess::transposition::Bound;
+
+    #[test]
+    fn setting_the_hash_size_clears_the_table() {
+        let mut uci = Uci::new();
+        uci.table
+            .lock()
+            .unwrap()
+            .store(1, 8, Bound::Exact, 50, None);
+        assert!(uci.table.lock().unwrap().probe(1).is_some());
+        uci.command("setoption name Hash value 1");
+        assert!(uci.table.lock().unwrap().probe(1).is_none());
+    }
+}
@@ -0,0 +1,371 @@
+use std::{
+    io::BufRead,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{
+    chess::{
+        game::GameResult,
+        piece::Sign,
+        search::{time_budget, SearchLimits},
+        square::AlgebraicMove,
+        standard::{fen::STARTING_FEN, magic, pgn::result_tag, BitBoardGame},
+    },
+    engine::{
+        algebraic, describe, line, shared_table, SharedTable, Thinking, DEFAULT_HASH, MAX_HASH,
+    },
+};
+
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+
+/// The time control set by `level`: moves per session, base time and increment.
+struct Level {
+    moves: usize,
+    base: Duration,
+    increment: Duration,
+}
+
+impl Level {
+    /// Reads the arguments of `level MPS BASE INC`, where the base time is in
+    /// minutes or minutes:seconds and the increment in seconds.
+    fn parse(arguments: &[&str]) -> Option<Self> {
+        let (moves, base, increment) = match arguments {
+            [moves, base, increment] => (moves, base, increment),
+            _ => return None,
+        };
+        let base = match base.split_once(':') {
+            Some((minutes, seconds)) => {
+                minutes.parse::<u64>().ok()? * 60 + seconds.parse::<u64>().ok()?
+            }
+            None => base.parse::<u64>().ok()? * 60,
+        };
+        Some(Level {
+            moves: moves.parse().ok()?,
+            base: Duration::from_secs(base),
+            increment: Duration::from_secs_f64(increment.parse().ok()?),
+        })
+    }
+}
+
+/// An engine speaking the Chess Engine Communication Protocol of XBoard and
+/// WinBoard. The engine plays the side given by `go`, or neither in force mode.
+pub struct XBoard {
+    game: Arc<Mutex<BitBoardGame>>,
+    engine_side: Option<Sign>,
+    depth: Option<usize>,
+    move_time: Option<Duration>,
+    level: Level,
+    clock: Option<Duration>,
+    post: bool,
+    table: SharedTable,
+    thinking: Option<(Thinking, Arc<AtomicBool>)>,
+}
+
+impl XBoard {
+    pub fn new() -> Self {
+        XBoard {
+            game: Arc::new(Mutex::new(BitBoardGame::from_fen(STARTING_FEN).unwrap())),
+            engine_side: Some(Sign::Negative),
+            depth: None,
+            move_time: None,
+            level: Level {
+                moves: 40,
+                base: Duration::from_secs(300),
+                increment: Duration::ZERO,
+            },
+            clock: None,
+            post: false,
+            table: shared_table(DEFAULT_HASH),
+            thinking: None,
+        }
+    }
+
+    /// Serves commands after the `xboard` command that selected the protocol, until
+    /// `quit` or the end of the input. The attack tables are filled first, so that
+    /// the first search does not spend its time on them.
+    pub fn run(&mut self, input: &mut impl BufRead) {
+        magic::init();
+        for line in input.lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            if !self.command(&line) {
+                break;
+            }
+        }
+        self.stop(true);
+    }
+
+    /// Handles a single command. Returns whether the engine should keep running.
+    pub fn command(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let (command, arguments) = match tokens.split_first() {
+            Some((command, arguments)) => (*command, arguments),
+            None => return true,
+        };
+        match command {
+            "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer"
+            | "name" | "rating" | "ics" | "otim" => (),
+            "protover" => {
+                println!(
+                    "feature myname=\"{} {}\" ping=1 setboard=1 usermove=1 time=1 draw=0 \
+                     sigint=0 sigterm=0 reuse=1 analyze=0 colors=0 san=0 memory=1",
+                    env!("CARGO_PKG_NAME"),
+                    env!("CARGO_PKG_VERSION")
+                );
+                println!("feature done=1");
+            }
+            "new" => {
+                self.stop(true);
+                *self.game.lock().unwrap() = BitBoardGame::from_fen(STARTING_FEN).unwrap();
+                self.engine_side = Some(Sign::Negative);
+                self.depth = None;
+                self.table.lock().unwrap().clear();
+            }
+            "force" => {
+                self.stop(true);
+                self.engine_side = None;
+            }
+            "go" => {
+                self.stop(true);
+                self.engine_side = Some(self.game.lock().unwrap().side_to_move());
+                self.think();
+            }
+            "playother" => {
+                self.stop(true);
+                self.engine_side = Some(-self.game.lock().unwrap().side_to_move());
+            }
+            "?" => self.stop(false),
+            "usermove" => match arguments.first() {
+                Some(text) => self.user_move(text),
+                None => println!("Error (missing move): usermove"),
+            },
+            "setboard" => {
+                self.stop(true);
+                match BitBoardGame::from_fen(&arguments.join(" ")) {
+                    Ok(game) => *self.game.lock().unwrap() = game,
+                    Err(error) => println!("tellusererror Illegal position: {}", error),
+                }
+            }
+            "level" => self.set_level(arguments),
+            "st" => match arguments.first().and_then(|seconds| seconds.parse().ok()) {
+                Some(seconds) => self.move_time = Some(Duration::from_secs(seconds)),
+                None => println!("Error (invalid time): {}", line),
+            },
+            "sd" => match arguments.first().and_then(|depth| depth.parse().ok()) {
+                Some(depth) => self.depth = Some(depth),
+                None => println!("Error (invalid depth): {}", line),
+            },
+            "time" => {
+                let centiseconds = arguments.first().and_then(|time| time.parse().ok());
+                self.clock = centiseconds.map(|time: u64| Duration::from_millis(time * 10));
+            }
+            "undo" | "remove" => {
+                self.stop(true);
+                let mut game = self.game.lock().unwrap();
+                for _ in 0..if command == "remove" { 2 } else { 1 } {
+                    game.undo();
+                }
+            }
+            "result" => {
+                self.stop(true);
+                self.engine_side = None;
+            }
+            "memory" => match arguments
+                .first()
+                .and_then(|megabytes| megabytes.parse::<usize>().ok())
+            {
+                // The interface offers the memory it allows in total, which may well be
+                // more than the table is ever given.
+                Some(megabytes) => {
+                    self.stop(true);
+                    self.table
+                        .lock()
+                        .unwrap()
+                        .resize(megabytes.clamp(1, MAX_HASH));
+                }
+                None => println!("Error (invalid memory): {}", line),
+            },
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "ping" => println!("pong {}", arguments.first().unwrap_or(&"")),
+            "quit" => return false,
+            _ if command.parse::<AlgebraicMove>().is_ok() => self.user_move(command),
+            _ => println!("Error (unknown command): {}", command),
+        }
+        true
+    }
+
+    fn user_move(&mut self, text: &str) {
+        self.stop(true);
+        let played = text.parse::<AlgebraicMove>().ok().and_then(|algebraic| {
+            let mut game = self.game.lock().unwrap();
+            game.play_move(&algebraic).ok()?;
+            Some(game.status())
+        });
+        match played {
+            Some(GameResult::Ongoing) => self.think(),
+            Some(result) => announce(result),
+            None => println!("Illegal move: {}", text),
+        }
+    }
+
+    /// Starts thinking if it is the engine's turn, playing the move it finds unless
+    /// the search is abandoned first.
+    fn think(&mut self) {
+        let game = self.game.lock().unwrap().clone();
+        if self.engine_side != Some(game.side_to_move()) || game.status() != GameResult::Ongoing {
+            return;
+        }
+        let mut limits = SearchLimits {
+            depth: self.depth,
+            time: self.move_time,
+        };
+        if limits.time.is_none() {
+            let played = game.fullmove_number() - 1;
+            let moves_to_go = match self.level.moves {
+                0 => None,
+                moves => Some(moves - played % moves),
+            };
+            limits.time = Some(time_budget(
+                self.clock.unwrap_or(self.level.base),
+                self.level.increment,
+                moves_to_go,
+                MOVE_OVERHEAD,
+            ));
+        }
+        let abandoned = Arc::new(AtomicBool::new(false));
+        let shared = Arc::clone(&self.game);
+        let flag = Arc::clone(&abandoned);
+        let post = self.post;
+        let thinking = Thinking::start(
+            game,
+            limits,
+            Arc::clone(&self.table),
+            false,
+            move |result| {
+                if post {
+                    println!(
+                        "{} {} {} {} {}",
+                        result.depth,
+                        result.score,
+                        result.time.as_millis() / 10,
+                        result.nodes,
+                        line(&result.pv, false)
+                    );
+                }
+            },
+            move |result| {
+                let algebraic = match result.best_move {
+                    Some(best_move) => algebraic(&best_move, false),
+                    None => return,
+                };
+                let mut game = shared.lock().unwrap();
+                if flag.load(Ordering::Relaxed) || game.play_move(&algebraic).is_err() {
+                    return;
+                }
+                println!("move {}", algebraic);
+                let status = game.status();
+                if status != GameResult::Ongoing {
+                    announce(status);
+                }
+            },
+        );
+        self.thinking = Some((thinking, abandoned));
+    }
+
+    /// Stops a running search, either playing the best move found so far or
+    /// abandoning it without moving.
+    fn stop(&mut self, abandon: bool) {
+        if let Some((thinking, abandoned)) = self.thinking.take() {
+            abandoned.store(abandon, Ordering::Relaxed);
+            thinking.stop();
+        }
+    }
+
+    fn set_level(&mut self, arguments: &[&str]) {
+        match Level::parse(arguments) {
+            Some(level) => {
+                self.level = level;
+                self.move_time = None;
+            }
+            None => println!(
+                "Error (invalid time control): level {}",
+                arguments.join(" ")
+            ),
+        }
+    }
+}
+
+/// Tells the interface how the game ended.
+fn announce(result: GameResult) {
+    if result != GameResult::Ongoing {
+        println!("{} {{{}}}", result_tag(result), describe(result));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::XBoard;
+    use crate::chess::{piece::Sign, standard::fen::STARTING_FEN, transposition::Bound};
+
+    const AFTER_E4: &str = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1";
+
+    fn fen(xboard: &XBoard) -> String {
+        xboard.game.lock().unwrap().to_fen()
+    }
+
+    #[test]
+    fn force_mode_takes_moves_without_answering() {
+        let mut xboard = XBoard::new();
+        xboard.command("force");
+        xboard.command("usermove e2e4");
+        assert!(xboard.thinking.is_none());
+        assert_eq!(fen(&xboard), AFTER_E4);
+        xboard.command("undo");
+        assert_eq!(fen(&xboard), STARTING_FEN);
+        xboard.command("usermove e2e5");
+        assert_eq!(fen(&xboard), STARTING_FEN);
+    }
+
+    #[test]
+    fn setboard_replaces_the_position() {
+        let mut xboard = XBoard::new();
+        xboard.command("force");
+        xboard.command(&format!("setboard {}", AFTER_E4));
+        assert_eq!(fen(&xboard), AFTER_E4);
+        xboard.command("setboard 8/8/8 w - - 0 1");
+        assert_eq!(fen(&xboard), AFTER_E4);
+    }
+
+    #[test]
+    fn go_plays_for_the_side_to_move() {
+        let mut xboard = XBoard::new();
+        xboard.command("sd 1");
+        xboard.command("force");
+        xboard.command("usermove e2e4");
+        xboard.command("go");
+        assert_eq!(xboard.engine_side, Some(Sign::Negative));
+        xboard.stop(false);
+        let game = xboard.game.lock().unwrap();
+        assert_eq!(game.side_to_move(), Sign::Positive);
+        assert_eq!(game.moves().len(), 2);
+    }
+
+    #[test]
+    fn memory_clears_the_table() {
+        let mut xboard = XBoard::new();
+        xboard
+            .table
+            .lock()
+            .unwrap()
+            .store(1, 8, Bound::Exact, 50, None);
+        xboard.command("memory 1");
+        assert!(xboard.table.lock().unwrap().probe(1).is_none());
+    }
+}